@@ -0,0 +1,67 @@
+use time::{now_utc, Duration};
+use rusqlite::Connection;
+
+use flights::Request;
+use flights::search::RawResponse;
+use Error;
+
+pub const DEFAULT_TTL_MINUTES: i64 = 0;
+
+pub struct Cache {
+    ttl: Duration
+}
+
+impl Cache {
+    pub fn new(ttl_minutes: i64) -> Self {
+        Cache { ttl: Duration::minutes(ttl_minutes) }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.ttl > Duration::zero()
+    }
+
+    /// Identical searches share a key: the provider and the search encoded as a QPX request.
+    pub fn key(provider: &str, request: &Request) -> Result<String, Error> {
+        let json = try!(request.to_json());
+
+        Ok(format!("{}:{}", provider, json))
+    }
+
+    pub fn get(&self, conn: &Connection, key: &str) -> Result<Option<RawResponse>, Error> {
+        if !self.is_enabled() { return Ok(None) }
+
+        let mut sql = try!(conn.prepare("SELECT query, body FROM cache WHERE key = ? AND created_at > ?")
+            .map_err(|err| Error::PreparingDbQuery(err.to_string())));
+
+        let expired_at = (now_utc() - self.ttl).to_timespec();
+        let mut rows = try!(sql.query(&[&key, &expired_at]).map_err(|err| Error::ExecutingDbQuery(err.to_string())));
+
+        match rows.next() {
+            Some(row) => {
+                let data = try!(row.map_err(|err| Error::UnwrappingDbRow(err.to_string())));
+                let body: Vec<u8> = data.get(1);
+
+                Ok(Some(RawResponse { query: data.get(0), body: String::from_utf8_lossy(&body).into_owned() }))
+            },
+            None => Ok(None)
+        }
+    }
+
+    pub fn put(&self, conn: &Connection, key: &str, response: &RawResponse) -> Result<(), Error> {
+        let expired_at = (now_utc() - self.ttl).to_timespec();
+
+        try!(conn.execute("DELETE FROM cache WHERE created_at <= ?", &[&expired_at])
+            .map_err(|err| Error::ExecutingDbQuery(err.to_string())));
+
+        if !self.is_enabled() { return Ok(()) }
+
+        let created_at = now_utc().to_timespec();
+
+        try!(conn.execute(
+            "INSERT OR REPLACE INTO cache (key, query, body, created_at) VALUES (?, ?, ?, ?)",
+            &[&key, &response.query, &response.body.as_bytes(), &created_at]
+            ).map_err(|err| Error::ExecutingDbQuery(err.to_string())));
+
+        Ok(())
+    }
+}
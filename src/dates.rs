@@ -0,0 +1,90 @@
+use std::collections::BTreeSet;
+
+use time::{strptime, at_utc, now_utc, Duration, Tm};
+
+use Error;
+
+const DATE_FORMAT: &'static str = "%Y-%m-%d";
+const WEEKDAYS: [&'static str; 7] = ["Sun", "Mon", "Tue", "Wed", "Thu", "Fri", "Sat"];
+pub const MAX_RANGE_DAYS: i64 = 366;
+
+pub fn parse_date(date: &str) -> Result<Tm, Error> {
+    let parsed = try!(strptime(date, DATE_FORMAT).map_err(|_| Error::InvalidDates(format!("{} is not a date like 2016-03-28", date))));
+    let normalized = at_utc(parsed.to_timespec());
+
+    // strptime happily accepts days like 2016-02-30, which would roll over into March
+    if format_date(&normalized) != date {
+        return Err(Error::InvalidDates(format!("{} is not a valid date", date)))
+    }
+
+    Ok(normalized)
+}
+
+pub fn format_date(date: &Tm) -> String {
+    date.strftime(DATE_FORMAT).unwrap().to_string()
+}
+
+pub fn today() -> String {
+    format_date(&now_utc())
+}
+
+pub fn parse_weekday(weekday: &str) -> Result<i32, Error> {
+    WEEKDAYS.iter().position(|day| day.eq_ignore_ascii_case(weekday))
+        .map(|index| index as i32)
+        .ok_or(Error::InvalidDates(format!("{} is not one of {}", weekday, WEEKDAYS.join(", "))))
+}
+
+pub fn range(from: &str, to: &str) -> Result<Vec<String>, Error> {
+    let from_date = try!(parse_date(from));
+    let to_date = try!(parse_date(to));
+    let days = (to_date.to_timespec() - from_date.to_timespec()).num_days();
+
+    if days < 0 {
+        return Err(Error::InvalidDates(format!("{} comes before {}", to, from)))
+    }
+
+    if days > MAX_RANGE_DAYS {
+        return Err(Error::InvalidDates(format!("{} to {} spans more than {} days", from, to, MAX_RANGE_DAYS)))
+    }
+
+    Ok((0..(days + 1)).map(|day| format_date(&at_utc(from_date.to_timespec() + Duration::days(day)))).collect())
+}
+
+pub fn add_days(date: &str, days: i64) -> Result<String, Error> {
+    let parsed = try!(parse_date(date));
+
+    Ok(format_date(&at_utc(parsed.to_timespec() + Duration::days(days))))
+}
+
+pub fn on_weekdays(dates: &[String], weekdays: &[i32]) -> Result<Vec<String>, Error> {
+    let mut matching_dates = vec!();
+
+    for date in dates {
+        if weekdays.contains(&try!(parse_date(date)).tm_wday) {
+            matching_dates.push(date.clone());
+        }
+    }
+
+    Ok(matching_dates)
+}
+
+pub fn flex(dates: &[String], flex_days: i64) -> Result<Vec<String>, Error> {
+    if flex_days < 0 || flex_days > MAX_RANGE_DAYS {
+        return Err(Error::InvalidDates(format!("flex_days {} has to be between 0 and {}", flex_days, MAX_RANGE_DAYS)))
+    }
+
+    let today = today();
+    let mut flexible_dates = BTreeSet::new();
+
+    for date in dates {
+        for day in -flex_days..(flex_days + 1) {
+            let flexible_date = try!(add_days(date, day));
+
+            if flexible_date >= today {
+                flexible_dates.insert(flexible_date);
+            }
+        }
+    }
+
+    Ok(flexible_dates.into_iter().collect())
+}
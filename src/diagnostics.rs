@@ -0,0 +1,118 @@
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use std::fmt::Result as FmtResult;
+
+use Error;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Severity {
+    Error,
+    Warning
+}
+
+#[derive(Debug, Clone)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub key: String,
+    pub line: Option<usize>,
+    pub message: String
+}
+
+impl Diagnostic {
+    pub fn error(key: &str, message: &str) -> Self {
+        Diagnostic { severity: Severity::Error, key: key.to_string(), line: None, message: message.to_string() }
+    }
+
+    pub fn warning(key: &str, message: &str) -> Self {
+        Diagnostic { severity: Severity::Warning, key: key.to_string(), line: None, message: message.to_string() }
+    }
+
+    pub fn from_error(key: &str, error: Error) -> Self {
+        match error {
+            Error::InvalidPassengers(message) | Error::InvalidDates(message) => Diagnostic::error(key, &message),
+            other => Diagnostic::error(key, &format!("{:?}", other))
+        }
+    }
+
+    pub fn is_error(&self) -> bool {
+        self.severity == Severity::Error
+    }
+}
+
+impl Display for Diagnostic {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        match self.severity {
+            Severity::Error => try!(write!(f, "error")),
+            Severity::Warning => try!(write!(f, "warning"))
+        };
+
+        if !self.key.is_empty() { try!(write!(f, ": {}", self.key)); }
+        if let Some(line) = self.line { try!(write!(f, " (line {})", line)); }
+
+        write!(f, ": {}", self.message)
+    }
+}
+
+pub fn child_key(parent: &str, child: &str) -> String {
+    if parent.is_empty() { child.to_string() } else { format!("{}.{}", parent, child) }
+}
+
+pub fn is_airport_code(code: &str) -> bool {
+    code.len() == 3 && code.chars().all(|c| c.is_ascii_uppercase())
+}
+
+/// Keys that aren't set explicitly point to the table they belong to.
+pub fn locate(source: &str, key: &str) -> Option<usize> {
+    let mut lines = HashMap::new();
+    let mut array_counts: HashMap<String, usize> = HashMap::new();
+    let mut table = String::new();
+
+    for (index, line) in source.lines().enumerate() {
+        let line = line.trim();
+
+        if line.starts_with("[[") {
+            let names = line.trim_left_matches('[').trim_right_matches(']');
+            table = table_key(names, true, &mut array_counts);
+        } else if line.starts_with("[") {
+            let names = line.trim_left_matches('[').trim_right_matches(']');
+            table = table_key(names, false, &mut array_counts);
+        } else if let Some(position) = line.find('=') {
+            let name = line[..position].trim().trim_matches('"');
+            lines.entry(child_key(&table, name)).or_insert(index + 1);
+            continue;
+        } else {
+            continue;
+        }
+
+        lines.entry(table.clone()).or_insert(index + 1);
+    }
+
+    let mut key = key.to_string();
+    loop {
+        if let Some(line) = lines.get(&key) { return Some(*line) }
+
+        match key.rfind(|c| c == '.' || c == '[') {
+            Some(position) => key.truncate(position),
+            None => return None
+        }
+    }
+}
+
+fn table_key(names: &str, array: bool, array_counts: &mut HashMap<String, usize>) -> String {
+    let names: Vec<&str> = names.split('.').map(|name| name.trim().trim_matches('"')).collect();
+    let mut key = String::new();
+
+    for (index, name) in names.iter().enumerate() {
+        key = child_key(&key, name);
+
+        if array && index == names.len() - 1 {
+            let count = array_counts.entry(key.clone()).or_insert(0);
+            key = format!("{}[{}]", key, count);
+            *count += 1;
+        } else if let Some(count) = array_counts.get(&key) {
+            key = format!("{}[{}]", key, count - 1);
+        }
+    }
+
+    key
+}
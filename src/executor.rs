@@ -0,0 +1,47 @@
+use std::cmp::{max, min};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::sync::mpsc::{channel, Receiver};
+use std::thread;
+
+use flights::Request;
+use flights::search::RawResponse;
+use providers::FareProvider;
+use Error;
+
+pub const DEFAULT_CONCURRENCY: usize = 4;
+
+#[derive(RustcDecodable, PartialEq)]
+pub struct ConcurrencyConfig {
+    pub limit: Option<usize>,
+    pub calls_per_second: Option<HashMap<String, f64>>
+}
+
+/// Only the network part runs on the threads, the caller decodes and stores the responses.
+pub fn execute(requests: Vec<Request>, provider: Arc<FareProvider>, concurrency: usize) -> Receiver<(Request, Result<RawResponse, Error>)> {
+    let (sender, receiver) = channel();
+    let threads = min(max(concurrency, 1), requests.len());
+    let queue = Arc::new(Mutex::new(requests.into_iter()));
+
+    for _ in 0..threads {
+        let queue = queue.clone();
+        let provider = provider.clone();
+        let sender = sender.clone();
+
+        thread::spawn(move || {
+            loop {
+                let next = queue.lock().unwrap().next();
+
+                let request = match next {
+                    Some(request) => request,
+                    None => break
+                };
+
+                let result = request.fetch(&*provider);
+                if sender.send((request, result)).is_err() { break }
+            }
+        });
+    }
+
+    receiver
+}
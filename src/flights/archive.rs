@@ -0,0 +1,94 @@
+use time::{now_utc, Timespec};
+use rusqlite::Connection;
+
+use flights::{Request, Offer};
+use providers;
+use Error;
+
+/// Kept so offers can be parsed again after the parser changed or failed.
+pub struct Archive {
+    pub id: Option<i64>,
+    pub request_id: i64,
+    pub provider: String,
+    pub query: String,
+    pub body: String,
+    pub created_at: Timespec
+}
+
+impl Archive {
+    pub fn new(request_id: i64, provider: &str, query: &str, body: &str) -> Self {
+        Archive {
+            id: None,
+            request_id: request_id,
+            provider: provider.to_string(),
+            query: query.to_string(),
+            body: body.to_string(),
+            created_at: now_utc().to_timespec()
+        }
+    }
+
+    pub fn create(&mut self, conn: &Connection) -> Result<(), Error> {
+        let mut sql = try!(conn.prepare(
+            "INSERT INTO archives
+                (
+                    request_id,
+                    provider,
+                    query,
+                    body,
+                    created_at
+                ) VALUES (?, ?, ?, ?, ?)"
+            ).map_err(|err| Error::PreparingDbQuery(err.to_string())));
+
+        try!(sql.execute(
+            &[
+                &self.request_id,
+                &self.provider,
+                &self.query,
+                &self.body.as_bytes(),
+                &self.created_at
+            ]).map_err(|err| Error::ExecutingDbQuery(err.to_string())));
+
+        self.id = Some(conn.last_insert_rowid());
+
+        Ok(())
+    }
+
+    pub fn load(conn: &Connection, request_id: Option<i64>) -> Result<Vec<Self>, Error> {
+        let mut sql = try!(conn.prepare(
+            "SELECT id, request_id, provider, query, body, created_at FROM archives
+            WHERE ? IS NULL OR request_id = ?
+            ORDER BY id"
+            ).map_err(|err| Error::PreparingDbQuery(err.to_string())));
+
+        let rows = try!(sql.query(&[&request_id, &request_id]).map_err(|err| Error::ExecutingDbQuery(err.to_string())));
+
+        let mut archives = Vec::new();
+        for row in rows {
+            let data = try!(row.map_err(|err| Error::UnwrappingDbRow(err.to_string())));
+            let body: Vec<u8> = data.get(4);
+
+            let archive = Archive {
+                id: data.get(0),
+                request_id: data.get(1),
+                provider: data.get(2),
+                query: data.get(3),
+                body: String::from_utf8_lossy(&body).into_owned(),
+                created_at: data.get(5)
+            };
+
+            archives.push(archive);
+        }
+
+        Ok(archives)
+    }
+
+    pub fn reparse(&self, conn: &Connection) -> Result<Vec<Offer>, Error> {
+        let mut fares = try!(providers::decode(&self.provider, &self.body));
+
+        try!(Request::update_provider_request_id(conn, self.request_id, &fares.provider_request_id));
+        try!(fares.reference.create(conn));
+        try!(Offer::replace_for_request(conn, self.request_id, &mut fares.offers));
+
+        Ok(fares.offers)
+    }
+}
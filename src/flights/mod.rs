@@ -1,6 +1,20 @@
 pub mod request;
 pub mod offer;
+pub mod passengers;
+pub mod archive;
+pub mod search;
+pub mod reference;
+pub mod store;
 
 pub type Request = request::Request;
 pub type Offer = offer::Offer;
-pub type Flight = offer::Flight;
+pub type Slice = offer::Slice;
+pub type Segment = offer::Segment;
+pub type Leg = offer::Leg;
+pub type Pricing = offer::Pricing;
+pub type Fare = offer::Fare;
+pub type OfferTax = offer::OfferTax;
+pub type Passengers = passengers::Passengers;
+pub type Archive = archive::Archive;
+pub type SearchResult = search::SearchResult;
+pub type Store<'a> = store::Store<'a>;
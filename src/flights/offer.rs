@@ -1,19 +1,17 @@
 use std::fmt::{Display, Formatter};
 use std::fmt::Result as FmtResult;
 
-use time::{strptime, at, Tm, Timespec};
-use rustc_serialize::json;
+use time::{at, Timespec};
 use rusqlite::Connection;
 
-use money;
 use Error;
 
 const PRETTY_TIME_FORMAT: &'static str = "%d.%m %H:%I";
-const ISO_TIME_FORMAT: &'static str = "%Y-%m-%dT%H:%M%z";
 
 pub struct Offer {
     pub id: Option<i64>,
-    pub request_id: i64,
+    pub request_id: Option<i64>,
+    pub provider_id: Option<String>,
     pub currency: String,
     pub base_price: f64,
     pub sale_price: f64,
@@ -21,12 +19,36 @@ pub struct Offer {
     pub total_price: f64,
     pub latest_ticketing_at: Timespec,
     pub refundable: bool,
-    pub flights: Vec<Flight>
+    pub stops: i64,
+    pub slices: Vec<Slice>,
+    pub pricings: Vec<Pricing>,
+    pub taxes: Vec<OfferTax>
 }
 
-pub struct Flight {
+pub struct Slice {
     pub id: Option<i64>,
     pub offer_id: Option<i64>,
+    pub duration: i64,
+    pub segments: Vec<Segment>
+}
+
+pub struct Segment {
+    pub id: Option<i64>,
+    pub slice_id: Option<i64>,
+    pub carrier: String,
+    pub number: String,
+    pub cabin: String,
+    pub booking_code: String,
+    pub booking_code_count: i64,
+    pub duration: i64,
+    pub connection_duration: Option<i64>,
+    pub carrier_name: Option<String>,
+    pub legs: Vec<Leg>
+}
+
+pub struct Leg {
+    pub id: Option<i64>,
+    pub segment_id: Option<i64>,
     pub origin: String,
     pub destination: String,
     pub departs_at: Timespec,
@@ -35,255 +57,356 @@ pub struct Flight {
     pub arrives_at_offset: i64,
     pub duration: i64,
     pub mileage: i64,
-    pub seat: String,
     pub aircraft: String,
+    pub meal: Option<String>,
+    pub origin_name: Option<String>,
+    pub destination_name: Option<String>,
+    pub aircraft_name: Option<String>
+}
+
+/// Prices are per passenger.
+pub struct Pricing {
+    pub id: Option<i64>,
+    pub offer_id: Option<i64>,
+    pub passenger_type: String,
+    pub passengers: i64,
+    pub currency: String,
+    pub base_price: f64,
+    pub sale_price: f64,
+    pub tax_price: f64,
+    pub total_price: f64,
+    pub fare_calculation: String,
+    pub fares: Vec<Fare>
+}
+
+pub struct Fare {
+    pub id: Option<i64>,
+    pub pricing_id: Option<i64>,
     pub carrier: String,
-    pub number: String
+    pub origin: String,
+    pub destination: String,
+    pub basis_code: String
 }
 
-#[derive(RustcDecodable)]
-struct SearchResponse {
-    trips: Trips
+pub struct OfferTax {
+    pub id: Option<i64>,
+    pub offer_id: Option<i64>,
+    pub passenger_type: String,
+    pub tax_id: String,
+    pub name: Option<String>,
+    pub code: String,
+    pub country: Option<String>,
+    pub charge_type: Option<String>,
+    pub amount: f64,
+    pub currency: String
 }
 
-impl SearchResponse {
-    pub fn to_offers(self, request_id: &i64) -> Result<Vec<Offer>, Error> {
-        let mut offers = vec!();
-        for option in self.trips.tripOption {
-            match option.to_offer(request_id) {
-                Ok(offer) => offers.push(offer),
-                _ => {}
+impl Offer {
+    pub fn create(&mut self, conn: &Connection) -> Result<(), Error> {
+        let transaction = try!(conn.transaction().map_err(|err| Error::CreatingTransaction(err.to_string())));
+
+        try!(self.insert(conn));
+
+        try!(transaction.commit().map_err(|err| Error::CommitingTransaction(err.to_string())));
+
+        Ok(())
+    }
+
+    fn insert(&mut self, conn: &Connection) -> Result<(), Error> {
+        let mut sql = try!(conn.prepare(
+            "INSERT INTO offers
+                (
+                    request_id,
+                    currency,
+                    base_price,
+                    sale_price,
+                    tax_price,
+                    total_price,
+                    latest_ticketing_at,
+                    refundable,
+                    stops,
+                    provider_id
+                ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)"
+            ).map_err(|err| Error::PreparingDbQuery(err.to_string())));
+
+        try!(sql.execute(
+            &[
+                &self.request_id,
+                &self.currency,
+                &self.base_price,
+                &self.sale_price,
+                &self.tax_price,
+                &self.total_price,
+                &self.latest_ticketing_at,
+                &self.refundable,
+                &self.stops,
+                &self.provider_id
+            ]).map_err(|err| Error::ExecutingDbQuery(err.to_string())));
+
+        self.id = Some(conn.last_insert_rowid());
+
+        for (position, slice) in self.slices.iter_mut().enumerate() {
+            slice.offer_id = self.id;
+            try!(slice.create(conn, position));
+        }
+
+        for pricing in &mut self.pricings {
+            pricing.offer_id = self.id;
+            try!(pricing.create(conn));
+        }
+
+        for tax in &mut self.taxes {
+            tax.offer_id = self.id;
+            try!(tax.create(conn));
+        }
+
+        Ok(())
+    }
+
+    pub fn find_by_provider_id(conn: &Connection, provider_id: &str) -> Result<Vec<Self>, Error> {
+        let mut sql = try!(conn.prepare(
+            "SELECT
+                id,
+                request_id,
+                provider_id,
+                currency,
+                base_price,
+                sale_price,
+                tax_price,
+                total_price,
+                latest_ticketing_at,
+                refundable,
+                stops
+            FROM offers WHERE provider_id = ? ORDER BY id"
+            ).map_err(|err| Error::PreparingDbQuery(err.to_string())));
+
+        let rows = try!(sql.query(&[&provider_id]).map_err(|err| Error::ExecutingDbQuery(err.to_string())));
+
+        let mut offers = Vec::new();
+        for row in rows {
+            let data = try!(row.map_err(|err| Error::UnwrappingDbRow(err.to_string())));
+            let id: i64 = data.get(0);
+
+            let offer = Offer {
+                id: Some(id),
+                request_id: data.get(1),
+                provider_id: data.get(2),
+                currency: data.get(3),
+                base_price: data.get(4),
+                sale_price: data.get(5),
+                tax_price: data.get(6),
+                total_price: data.get(7),
+                latest_ticketing_at: data.get(8),
+                refundable: data.get(9),
+                stops: data.get(10),
+                slices: try!(Slice::load_for_offer(conn, id)),
+                pricings: try!(Pricing::load_for_offer(conn, id)),
+                taxes: try!(OfferTax::load_for_offer(conn, id))
             };
+
+            offers.push(offer);
         }
 
         Ok(offers)
     }
-}
 
-#[derive(RustcDecodable)]
-#[allow(non_snake_case)]
-struct Trips {
-    requestId: String,
-    data: TripsData,
-    tripOption: Vec<TripOption>
-}
+    /// Replaces the offers of a request at once, so a failed insert keeps the old ones.
+    pub fn replace_for_request(conn: &Connection, request_id: i64, offers: &mut [Offer]) -> Result<(), Error> {
+        let transaction = try!(conn.transaction().map_err(|err| Error::CreatingTransaction(err.to_string())));
 
-#[derive(RustcDecodable)]
-struct TripsData {
-    airport: Vec<Airport>,
-    city: Vec<City>,
-    aircraft: Vec<Aircraft>,
-    tax: Vec<Tax>,
-    carrier: Vec<Carrier>
-}
+        try!(Offer::delete_for_request(conn, request_id));
 
-#[derive(RustcDecodable)]
-struct Airport {
-    code: String,
-    city: String,
-    name: String
-}
+        for offer in offers {
+            offer.request_id = Some(request_id);
+            try!(offer.insert(conn));
+        }
 
-#[derive(RustcDecodable)]
-struct City {
-    code: String,
-    name: String
-}
+        try!(transaction.commit().map_err(|err| Error::CommitingTransaction(err.to_string())));
 
-#[derive(RustcDecodable)]
-struct Aircraft {
-    code: String,
-    name: String
-}
+        Ok(())
+    }
 
-#[derive(RustcDecodable)]
-struct Tax {
-    id: String,
-    name: String
-}
+    fn delete_for_request(conn: &Connection, request_id: i64) -> Result<(), Error> {
+        try!(conn.execute(
+            "DELETE FROM legs WHERE segment_id IN
+                (SELECT segments.id FROM segments
+                JOIN offer_slices ON offer_slices.id = segments.slice_id
+                JOIN offers ON offers.id = offer_slices.offer_id
+                WHERE offers.request_id = ?)",
+            &[&request_id]).map_err(|err| Error::ExecutingDbQuery(err.to_string())));
+
+        try!(conn.execute(
+            "DELETE FROM segments WHERE slice_id IN
+                (SELECT offer_slices.id FROM offer_slices
+                JOIN offers ON offers.id = offer_slices.offer_id
+                WHERE offers.request_id = ?)",
+            &[&request_id]).map_err(|err| Error::ExecutingDbQuery(err.to_string())));
+
+        try!(conn.execute(
+            "DELETE FROM fares WHERE pricing_id IN
+                (SELECT pricings.id FROM pricings
+                JOIN offers ON offers.id = pricings.offer_id
+                WHERE offers.request_id = ?)",
+            &[&request_id]).map_err(|err| Error::ExecutingDbQuery(err.to_string())));
+
+        try!(conn.execute(
+            "DELETE FROM pricings WHERE offer_id IN (SELECT id FROM offers WHERE request_id = ?)",
+            &[&request_id]).map_err(|err| Error::ExecutingDbQuery(err.to_string())));
+
+        try!(conn.execute(
+            "DELETE FROM offer_slices WHERE offer_id IN (SELECT id FROM offers WHERE request_id = ?)",
+            &[&request_id]).map_err(|err| Error::ExecutingDbQuery(err.to_string())));
+
+        try!(conn.execute(
+            "DELETE FROM offer_taxes WHERE offer_id IN (SELECT id FROM offers WHERE request_id = ?)",
+            &[&request_id]).map_err(|err| Error::ExecutingDbQuery(err.to_string())));
+
+        try!(conn.execute(
+            "DELETE FROM offers WHERE request_id = ?",
+            &[&request_id]).map_err(|err| Error::ExecutingDbQuery(err.to_string())));
 
-#[derive(RustcDecodable)]
-struct Carrier {
-    code: String,
-    name: String
+        Ok(())
+    }
 }
 
-#[derive(RustcDecodable)]
-#[allow(non_snake_case)]
-struct TripOption {
-    saleTotal: String,
-    id: String,
-    slice: Vec<Slice>,
-    pricing: Vec<Pricing>
-}
+impl Slice {
+    pub fn create(&mut self, conn: &Connection, position: usize) -> Result<(), Error> {
+        try!(conn.execute(
+            "INSERT INTO offer_slices (offer_id, position, duration) VALUES (?, ?, ?)",
+            &[&self.offer_id, &(position as i64), &self.duration]
+            ).map_err(|err| Error::ExecutingDbQuery(err.to_string())));
 
-#[derive(RustcDecodable)]
-struct Slice {
-    duration: i64,
-    segment: Vec<Segment>
-}
+        self.id = Some(conn.last_insert_rowid());
 
-#[derive(RustcDecodable)]
-#[allow(non_snake_case)]
-struct Segment {
-    duration: i64,
-    flight: GoogleFlight,
-    id: String,
-    cabin: String,
-    bookingCode: String,
-    bookingCodeCount: i64,
-    leg: Vec<Leg>,
-    connectionDuration: Option<i64>
-}
+        for (position, segment) in self.segments.iter_mut().enumerate() {
+            segment.slice_id = self.id;
+            try!(segment.create(conn, position));
+        }
 
-#[derive(RustcDecodable)]
-#[allow(non_snake_case)]
-struct Leg {
-    id: String,
-    aircraft: String,
-    arrivalTime: String,
-    departureTime: String,
-    origin: String,
-    destination: String,
-    duration: i64,
-    mileage: i64,
-    meal: Option<String>
-}
+        Ok(())
+    }
 
-#[derive(RustcDecodable)]
-struct GoogleFlight {
-    carrier: String,
-    number: String
-}
+    fn load_for_offer(conn: &Connection, offer_id: i64) -> Result<Vec<Self>, Error> {
+        let mut sql = try!(conn.prepare("SELECT id, duration FROM offer_slices WHERE offer_id = ? ORDER BY position")
+            .map_err(|err| Error::PreparingDbQuery(err.to_string())));
 
-#[derive(RustcDecodable)]
-#[allow(non_snake_case)]
-struct Pricing {
-    baseFareTotal: String,
-    saleFareTotal: String,
-    saleTaxTotal: String,
-    saleTotal: String,
-    fareCalculation: String,
-    latestTicketingTime: String,
-    ptc: String,
-    refundable: Option<bool>
-}
+        let rows = try!(sql.query(&[&offer_id]).map_err(|err| Error::ExecutingDbQuery(err.to_string())));
 
-impl TripOption {
-    pub fn to_offer(self, request_id: &i64) -> Result<Offer, Error> {
-        let mut flights: Vec<Flight> = vec!();
-
-        for slice in self.slice {
-            for segment in slice.segment {
-                let carrier = &segment.flight.carrier;
-                let number = &segment.flight.number;
-                let seat = &segment.cabin;
-
-                for leg in segment.leg {
-                    let departs_at = try!(parse_time(leg.departureTime));
-                    let arrives_at = try!(parse_time(leg.arrivalTime));
-
-                    let flight = Flight {
-                        id: None,
-                        offer_id: None,
-                        origin: leg.origin,
-                        destination: leg.destination,
-                        departs_at: departs_at.to_timespec(),
-                        departs_at_offset: departs_at.tm_utcoff as i64,
-                        arrives_at: arrives_at.to_timespec(),
-                        arrives_at_offset: arrives_at.tm_utcoff as i64,
-                        duration: leg.duration,
-                        mileage: leg.mileage,
-                        seat: seat.to_string(),
-                        aircraft: leg.aircraft,
-                        carrier: carrier.to_string(),
-                        number: number.to_string()
-                    };
-
-                    flights.push(flight);
-                }
-            }
+        let mut slices = Vec::new();
+        for row in rows {
+            let data = try!(row.map_err(|err| Error::UnwrappingDbRow(err.to_string())));
+            let id: i64 = data.get(0);
+
+            slices.push(Slice {
+                id: Some(id),
+                offer_id: Some(offer_id),
+                duration: data.get(1),
+                segments: try!(Segment::load_for_slice(conn, id))
+            });
         }
 
-        let pricing = try!(self.pricing.get(0).ok_or(Error::NoPricing));
-
-        let (base_price, _) = try!(money::parse(&pricing.baseFareTotal));
-        let (sale_price, _) = try!(money::parse(&pricing.saleFareTotal));
-        let (tax_price, _) = try!(money::parse(&pricing.saleTaxTotal));
-        let (total_price, currency) = try!(money::parse(&pricing.saleTotal));
-
-        let offer = Offer {
-            id: None,
-            request_id: request_id.clone(),
-            currency: currency.to_string(),
-            base_price: base_price,
-            sale_price: sale_price,
-            tax_price: tax_price,
-            total_price: total_price,
-            latest_ticketing_at: try!(parse_time(pricing.latestTicketingTime.clone())).to_timespec(),
-            refundable: pricing.refundable.unwrap_or(false),
-            flights: flights
-        };
-
-        Ok(offer)
+        Ok(slices)
     }
-}
 
-impl Offer {
-    pub fn from_json(json: String, request_id: i64) -> Result<Vec<Self>, Error> {
-        let price_response: SearchResponse = try!(json::decode(&json).map_err(|_| Error::DecodingJson(json)));
-
-        price_response.to_offers(&request_id)
+    pub fn origin(&self) -> Option<&Leg> {
+        self.segments.first().and_then(|segment| segment.legs.first())
     }
 
-    pub fn create(&mut self, conn: &Connection) -> Result<(), Error> {
-        let transaction = try!(conn.transaction().map_err(|err| Error::CreatingTransaction(err.to_string())));
+    pub fn destination(&self) -> Option<&Leg> {
+        self.segments.last().and_then(|segment| segment.legs.last())
+    }
+}
 
+impl Segment {
+    pub fn create(&mut self, conn: &Connection, position: usize) -> Result<(), Error> {
         let mut sql = try!(conn.prepare(
-            "INSERT INTO offers
+            "INSERT INTO segments
                 (
-                    request_id,
-                    currency,
-                    base_price,
-                    sale_price,
-                    tax_price,
-                    total_price,
-                    latest_ticketing_at,
-                    refundable
-                ) VALUES (?, ?, ?, ?, ?, ?, ?, ?)"
+                    slice_id,
+                    position,
+                    carrier,
+                    number,
+                    cabin,
+                    booking_code,
+                    booking_code_count,
+                    duration,
+                    connection_duration
+                ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)"
             ).map_err(|err| Error::PreparingDbQuery(err.to_string())));
 
         try!(sql.execute(
             &[
-                &self.request_id,
-                &self.currency,
-                &self.base_price,
-                &self.sale_price,
-                &self.tax_price,
-                &self.total_price,
-                &self.latest_ticketing_at,
-                &self.refundable
+                &self.slice_id,
+                &(position as i64),
+                &self.carrier,
+                &self.number,
+                &self.cabin,
+                &self.booking_code,
+                &self.booking_code_count,
+                &self.duration,
+                &self.connection_duration
             ]).map_err(|err| Error::ExecutingDbQuery(err.to_string())));
 
         self.id = Some(conn.last_insert_rowid());
 
-        for flight in &mut self.flights {
-            flight.offer_id = self.id;
-            try!(flight.create(conn));
+        for (position, leg) in self.legs.iter_mut().enumerate() {
+            leg.segment_id = self.id;
+            try!(leg.create(conn, position));
         }
 
-        try!(transaction.commit().map_err(|err| Error::CommitingTransaction(err.to_string())));
-
         Ok(())
     }
+
+    fn load_for_slice(conn: &Connection, slice_id: i64) -> Result<Vec<Self>, Error> {
+        let mut sql = try!(conn.prepare(
+            "SELECT
+                segments.id,
+                segments.carrier,
+                segments.number,
+                segments.cabin,
+                segments.booking_code,
+                segments.booking_code_count,
+                segments.duration,
+                segments.connection_duration,
+                carriers.name
+            FROM segments
+            LEFT JOIN carriers ON carriers.code = segments.carrier
+            WHERE segments.slice_id = ? ORDER BY segments.position"
+            ).map_err(|err| Error::PreparingDbQuery(err.to_string())));
+
+        let rows = try!(sql.query(&[&slice_id]).map_err(|err| Error::ExecutingDbQuery(err.to_string())));
+
+        let mut segments = Vec::new();
+        for row in rows {
+            let data = try!(row.map_err(|err| Error::UnwrappingDbRow(err.to_string())));
+            let id: i64 = data.get(0);
+
+            segments.push(Segment {
+                id: Some(id),
+                slice_id: Some(slice_id),
+                carrier: data.get(1),
+                number: data.get(2),
+                cabin: data.get(3),
+                booking_code: data.get(4),
+                booking_code_count: data.get(5),
+                duration: data.get(6),
+                connection_duration: data.get(7),
+                carrier_name: data.get(8),
+                legs: try!(Leg::load_for_segment(conn, id))
+            });
+        }
+
+        Ok(segments)
+    }
 }
 
-impl Flight {
-    pub fn create(&mut self, conn: &Connection) -> Result<(), Error> {
+impl Leg {
+    pub fn create(&mut self, conn: &Connection, position: usize) -> Result<(), Error> {
         let mut sql = try!(conn.prepare(
-            "INSERT INTO flights
+            "INSERT INTO legs
                 (
-                    offer_id,
+                    segment_id,
+                    position,
                     origin,
                     destination,
                     departs_at,
@@ -292,16 +415,15 @@ impl Flight {
                     arrives_at_offset,
                     duration,
                     mileage,
-                    seat,
                     aircraft,
-                    carrier,
-                    number
-                ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)"
+                    meal
+                ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)"
             ).map_err(|err| Error::PreparingDbQuery(err.to_string())));
 
         try!(sql.execute(
             &[
-                &self.offer_id,
+                &self.segment_id,
+                &(position as i64),
                 &self.origin,
                 &self.destination,
                 &self.departs_at,
@@ -310,32 +432,298 @@ impl Flight {
                 &self.arrives_at_offset,
                 &self.duration,
                 &self.mileage,
-                &self.seat,
                 &self.aircraft,
-                &self.carrier,
-                &self.number
+                &self.meal
             ]).map_err(|err| Error::ExecutingDbQuery(err.to_string())));
 
         self.id = Some(conn.last_insert_rowid());
 
         Ok(())
     }
+
+    fn load_for_segment(conn: &Connection, segment_id: i64) -> Result<Vec<Self>, Error> {
+        let mut sql = try!(conn.prepare(
+            "SELECT
+                id,
+                origin,
+                destination,
+                departs_at,
+                departs_at_offset,
+                arrives_at,
+                arrives_at_offset,
+                duration,
+                mileage,
+                aircraft,
+                meal,
+                origin_name,
+                destination_name,
+                aircraft_name
+            FROM leg_details WHERE segment_id = ? ORDER BY position"
+            ).map_err(|err| Error::PreparingDbQuery(err.to_string())));
+
+        let rows = try!(sql.query(&[&segment_id]).map_err(|err| Error::ExecutingDbQuery(err.to_string())));
+
+        let mut legs = Vec::new();
+        for row in rows {
+            let data = try!(row.map_err(|err| Error::UnwrappingDbRow(err.to_string())));
+
+            legs.push(Leg {
+                id: data.get(0),
+                segment_id: Some(segment_id),
+                origin: data.get(1),
+                destination: data.get(2),
+                departs_at: data.get(3),
+                departs_at_offset: data.get(4),
+                arrives_at: data.get(5),
+                arrives_at_offset: data.get(6),
+                duration: data.get(7),
+                mileage: data.get(8),
+                aircraft: data.get(9),
+                meal: data.get(10),
+                origin_name: data.get(11),
+                destination_name: data.get(12),
+                aircraft_name: data.get(13)
+            });
+        }
+
+        Ok(legs)
+    }
+}
+
+impl Pricing {
+    pub fn create(&mut self, conn: &Connection) -> Result<(), Error> {
+        let mut sql = try!(conn.prepare(
+            "INSERT INTO pricings
+                (
+                    offer_id,
+                    passenger_type,
+                    passengers,
+                    currency,
+                    base_price,
+                    sale_price,
+                    tax_price,
+                    total_price,
+                    fare_calculation
+                ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)"
+            ).map_err(|err| Error::PreparingDbQuery(err.to_string())));
+
+        try!(sql.execute(
+            &[
+                &self.offer_id,
+                &self.passenger_type,
+                &self.passengers,
+                &self.currency,
+                &self.base_price,
+                &self.sale_price,
+                &self.tax_price,
+                &self.total_price,
+                &self.fare_calculation
+            ]).map_err(|err| Error::ExecutingDbQuery(err.to_string())));
+
+        self.id = Some(conn.last_insert_rowid());
+
+        for fare in &mut self.fares {
+            fare.pricing_id = self.id;
+            try!(fare.create(conn));
+        }
+
+        Ok(())
+    }
+
+    fn load_for_offer(conn: &Connection, offer_id: i64) -> Result<Vec<Self>, Error> {
+        let mut sql = try!(conn.prepare(
+            "SELECT
+                id,
+                passenger_type,
+                passengers,
+                currency,
+                base_price,
+                sale_price,
+                tax_price,
+                total_price,
+                fare_calculation
+            FROM pricings WHERE offer_id = ? ORDER BY id"
+            ).map_err(|err| Error::PreparingDbQuery(err.to_string())));
+
+        let rows = try!(sql.query(&[&offer_id]).map_err(|err| Error::ExecutingDbQuery(err.to_string())));
+
+        let mut pricings = Vec::new();
+        for row in rows {
+            let data = try!(row.map_err(|err| Error::UnwrappingDbRow(err.to_string())));
+            let id: i64 = data.get(0);
+
+            pricings.push(Pricing {
+                id: Some(id),
+                offer_id: Some(offer_id),
+                passenger_type: data.get(1),
+                passengers: data.get(2),
+                currency: data.get(3),
+                base_price: data.get(4),
+                sale_price: data.get(5),
+                tax_price: data.get(6),
+                total_price: data.get(7),
+                fare_calculation: data.get(8),
+                fares: try!(Fare::load_for_pricing(conn, id))
+            });
+        }
+
+        Ok(pricings)
+    }
+
+    pub fn basis_codes(&self) -> Vec<&str> {
+        self.fares.iter().map(|fare| fare.basis_code.as_str()).collect()
+    }
+}
+
+impl Fare {
+    pub fn create(&mut self, conn: &Connection) -> Result<(), Error> {
+        try!(conn.execute(
+            "INSERT INTO fares (pricing_id, carrier, origin, destination, basis_code) VALUES (?, ?, ?, ?, ?)",
+            &[&self.pricing_id, &self.carrier, &self.origin, &self.destination, &self.basis_code]
+            ).map_err(|err| Error::ExecutingDbQuery(err.to_string())));
+
+        self.id = Some(conn.last_insert_rowid());
+
+        Ok(())
+    }
+
+    fn load_for_pricing(conn: &Connection, pricing_id: i64) -> Result<Vec<Self>, Error> {
+        let mut sql = try!(conn.prepare("SELECT id, carrier, origin, destination, basis_code FROM fares WHERE pricing_id = ? ORDER BY id")
+            .map_err(|err| Error::PreparingDbQuery(err.to_string())));
+
+        let rows = try!(sql.query(&[&pricing_id]).map_err(|err| Error::ExecutingDbQuery(err.to_string())));
+
+        let mut fares = Vec::new();
+        for row in rows {
+            let data = try!(row.map_err(|err| Error::UnwrappingDbRow(err.to_string())));
+
+            fares.push(Fare {
+                id: data.get(0),
+                pricing_id: Some(pricing_id),
+                carrier: data.get(1),
+                origin: data.get(2),
+                destination: data.get(3),
+                basis_code: data.get(4)
+            });
+        }
+
+        Ok(fares)
+    }
+}
+
+impl OfferTax {
+    pub fn create(&mut self, conn: &Connection) -> Result<(), Error> {
+        let mut sql = try!(conn.prepare(
+            "INSERT INTO offer_taxes
+                (
+                    offer_id,
+                    passenger_type,
+                    tax_id,
+                    name,
+                    code,
+                    country,
+                    charge_type,
+                    amount,
+                    currency
+                ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)"
+            ).map_err(|err| Error::PreparingDbQuery(err.to_string())));
+
+        try!(sql.execute(
+            &[
+                &self.offer_id,
+                &self.passenger_type,
+                &self.tax_id,
+                &self.name,
+                &self.code,
+                &self.country,
+                &self.charge_type,
+                &self.amount,
+                &self.currency
+            ]).map_err(|err| Error::ExecutingDbQuery(err.to_string())));
+
+        self.id = Some(conn.last_insert_rowid());
+
+        Ok(())
+    }
+
+    fn load_for_offer(conn: &Connection, offer_id: i64) -> Result<Vec<Self>, Error> {
+        let mut sql = try!(conn.prepare(
+            "SELECT
+                id,
+                passenger_type,
+                tax_id,
+                name,
+                code,
+                country,
+                charge_type,
+                amount,
+                currency
+            FROM offer_taxes WHERE offer_id = ? ORDER BY id"
+            ).map_err(|err| Error::PreparingDbQuery(err.to_string())));
+
+        let rows = try!(sql.query(&[&offer_id]).map_err(|err| Error::ExecutingDbQuery(err.to_string())));
+
+        let mut taxes = Vec::new();
+        for row in rows {
+            let data = try!(row.map_err(|err| Error::UnwrappingDbRow(err.to_string())));
+
+            taxes.push(OfferTax {
+                id: data.get(0),
+                offer_id: Some(offer_id),
+                passenger_type: data.get(1),
+                tax_id: data.get(2),
+                name: data.get(3),
+                code: data.get(4),
+                country: data.get(5),
+                charge_type: data.get(6),
+                amount: data.get(7),
+                currency: data.get(8)
+            });
+        }
+
+        Ok(taxes)
+    }
 }
 
 impl Display for Offer {
     fn fmt(&self, f: &mut Formatter) -> FmtResult {
         try!(write!(f, "PRICE: {}{}", self.total_price, self.currency));
-        try!(writeln!(f, " ({} + {}) / REFUNDABLE: {} / LATEST: {}", self.base_price, self.tax_price, self.refundable, format_time(self.latest_ticketing_at, None)));
+        try!(writeln!(f, " ({} + {}) / REFUNDABLE: {} / STOPS: {} / LATEST: {}", self.base_price, self.tax_price, self.refundable, self.stops, format_time(self.latest_ticketing_at, None)));
 
-        for flight in &self.flights {
-            try!(write!(f, "{}, {} ---> {}, {}", flight.origin, format_time(flight.departs_at, Some(flight.departs_at_offset)), flight.destination, format_time(flight.arrives_at, Some(flight.arrives_at_offset))));
-            try!(writeln!(f, " ({}{}, {})", flight.carrier, flight.number, flight.seat));
+        for pricing in &self.pricings {
+            try!(writeln!(f, "{} x{}: {}{} ({} + {}) / FARES: {}", pricing.passenger_type, pricing.passengers, pricing.total_price, pricing.currency, pricing.base_price, pricing.tax_price, pricing.basis_codes().join(", ")));
+        }
+
+        for (index, slice) in self.slices.iter().enumerate() {
+            try!(writeln!(f, "SLICE {} / DURATION: {}", index + 1, format_duration(slice.duration)));
+
+            for segment in &slice.segments {
+                for leg in &segment.legs {
+                    let origin = name_or_code(&leg.origin_name, &leg.origin);
+                    let destination = name_or_code(&leg.destination_name, &leg.destination);
+
+                    try!(write!(f, "{}, {} ---> {}, {}", origin, format_time(leg.departs_at, Some(leg.departs_at_offset)), destination, format_time(leg.arrives_at, Some(leg.arrives_at_offset))));
+                    try!(writeln!(f, " ({}, {}{}, {}, {})", name_or_code(&segment.carrier_name, &segment.carrier), segment.carrier, segment.number, segment.cabin, name_or_code(&leg.aircraft_name, &leg.aircraft)));
+                }
+
+                if let Some(connection_duration) = segment.connection_duration {
+                    try!(writeln!(f, "CONNECTION: {}", format_duration(connection_duration)));
+                }
+            }
         }
 
         Ok(())
     }
 }
 
+fn name_or_code<'a>(name: &'a Option<String>, code: &'a str) -> &'a str {
+    name.as_ref().map(|name| name.as_str()).unwrap_or(code)
+}
+
+fn format_duration(minutes: i64) -> String {
+    format!("{}h{:02}", minutes / 60, minutes % 60)
+}
+
 fn format_time(timespec: Timespec, utc_offset: Option<i64>) -> String {
     let mut time = at(timespec);
 
@@ -343,7 +731,3 @@ fn format_time(timespec: Timespec, utc_offset: Option<i64>) -> String {
 
     time.strftime(PRETTY_TIME_FORMAT).unwrap().to_string()
 }
-
-fn parse_time(time: String) -> Result<Tm, Error> {
-    strptime(&time, ISO_TIME_FORMAT).map_err(|_| Error::ParsingTime(time))
-}
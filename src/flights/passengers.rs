@@ -0,0 +1,45 @@
+use Error;
+
+const MAX_PASSENGERS: u8 = 9;
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct Passengers {
+    pub adults: u8,
+    pub children: u8,
+    pub seniors: u8,
+    pub infants_in_lap: u8,
+    pub infants_in_seat: u8
+}
+
+impl Passengers {
+    pub fn single_adult() -> Self {
+        Passengers {
+            adults: 1,
+            children: 0,
+            seniors: 0,
+            infants_in_lap: 0,
+            infants_in_seat: 0
+        }
+    }
+
+    pub fn total(&self) -> usize {
+        self.adults as usize + self.children as usize + self.seniors as usize +
+            self.infants_in_lap as usize + self.infants_in_seat as usize
+    }
+
+    pub fn validate(&self) -> Result<(), Error> {
+        if self.adults == 0 && self.seniors == 0 {
+            return Err(Error::InvalidPassengers("at least one adult or senior is required".to_string()))
+        }
+
+        if self.total() > MAX_PASSENGERS as usize {
+            return Err(Error::InvalidPassengers(format!("no more than {} passengers are allowed", MAX_PASSENGERS)))
+        }
+
+        if self.infants_in_lap as usize > self.adults as usize + self.seniors as usize {
+            return Err(Error::InvalidPassengers("every infant in lap needs an adult or senior".to_string()))
+        }
+
+        Ok(())
+    }
+}
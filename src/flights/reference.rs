@@ -0,0 +1,100 @@
+use rusqlite::Connection;
+
+use flights::Offer;
+use Error;
+
+#[derive(Default)]
+pub struct ReferenceData {
+    pub airports: Vec<Airport>,
+    pub cities: Vec<City>,
+    pub carriers: Vec<Carrier>,
+    pub aircraft: Vec<Aircraft>,
+    pub taxes: Vec<Tax>
+}
+
+pub struct Airport {
+    pub code: String,
+    pub city: String,
+    pub name: String
+}
+
+pub struct City {
+    pub code: String,
+    pub name: String
+}
+
+pub struct Carrier {
+    pub code: String,
+    pub name: String
+}
+
+pub struct Aircraft {
+    pub code: String,
+    pub name: String
+}
+
+pub struct Tax {
+    pub id: String,
+    pub name: String
+}
+
+impl ReferenceData {
+    pub fn create(&self, conn: &Connection) -> Result<(), Error> {
+        let transaction = try!(conn.transaction().map_err(|err| Error::CreatingTransaction(err.to_string())));
+
+        for city in &self.cities {
+            try!(upsert(conn, "INSERT OR REPLACE INTO cities (code, name) VALUES (?, ?)", &city.code, &city.name));
+        }
+
+        for airport in &self.airports {
+            try!(conn.execute(
+                "INSERT OR REPLACE INTO airports (code, city_code, name) VALUES (?, ?, ?)",
+                &[&airport.code, &airport.city, &airport.name]
+                ).map_err(|err| Error::ExecutingDbQuery(err.to_string())));
+        }
+
+        for carrier in &self.carriers {
+            try!(upsert(conn, "INSERT OR REPLACE INTO carriers (code, name) VALUES (?, ?)", &carrier.code, &carrier.name));
+        }
+
+        for aircraft in &self.aircraft {
+            try!(upsert(conn, "INSERT OR REPLACE INTO aircraft (code, name) VALUES (?, ?)", &aircraft.code, &aircraft.name));
+        }
+
+        for tax in &self.taxes {
+            try!(upsert(conn, "INSERT OR REPLACE INTO taxes (id, name) VALUES (?, ?)", &tax.id, &tax.name));
+        }
+
+        try!(transaction.commit().map_err(|err| Error::CommitingTransaction(err.to_string())));
+
+        Ok(())
+    }
+
+    pub fn annotate(&self, offers: &mut [Offer]) {
+        for offer in offers {
+            for segment in offer.slices.iter_mut().flat_map(|slice| slice.segments.iter_mut()) {
+                segment.carrier_name = self.carriers.iter().find(|carrier| carrier.code == segment.carrier).map(|carrier| carrier.name.clone());
+
+                for leg in &mut segment.legs {
+                    leg.origin_name = self.airport_name(&leg.origin);
+                    leg.destination_name = self.airport_name(&leg.destination);
+                    leg.aircraft_name = self.aircraft.iter().find(|aircraft| aircraft.code == leg.aircraft).map(|aircraft| aircraft.name.clone());
+                }
+            }
+
+            for tax in &mut offer.taxes {
+                tax.name = self.taxes.iter().find(|reference| reference.id == tax.tax_id).map(|reference| reference.name.clone());
+            }
+        }
+    }
+
+    fn airport_name(&self, code: &str) -> Option<String> {
+        self.airports.iter().find(|airport| airport.code == code).map(|airport| airport.name.clone())
+    }
+}
+
+fn upsert(conn: &Connection, sql: &str, code: &str, name: &str) -> Result<(), Error> {
+    try!(conn.execute(sql, &[&code, &name]).map_err(|err| Error::ExecutingDbQuery(err.to_string())));
+
+    Ok(())
+}
@@ -1,143 +1,136 @@
-use std::io::Read;
-
-use rustc_serialize::json;
-use hyper::Client;
-use hyper::header::{Connection, ContentType};
-use hyper::status::StatusCode;
-use mockito::url::Url;
 use time::{now_utc, Timespec, Duration};
 use rusqlite::Connection as DbConnection;
+use rusqlite::types::ToSql;
 
-use flights::Offer;
-use Session;
+use flights::{Passengers, SearchResult};
+use flights::search::RawResponse;
+use providers::{qpx, FareProvider};
 use Error;
 
-const SEARCH_URL: &'static str = "https://www.googleapis.com/qpxExpress/v1/trips/search";
-const PASSENGER_COUNT_KIND: &'static str = "qpxexpress#passengerCounts";
-const SLICE_KIND: &'static str = "qpxexpress#sliceInput";
-
 pub struct Request {
     pub id: Option<i64>,
     pub name: String,
     pub created_at: Timespec,
-    google_search_request: Option<GoogleSearchRequest>
-}
-
-#[derive(RustcEncodable)]
-struct GoogleSearchRequest {
-    request: GoogleRequest
-}
-
-#[derive(RustcEncodable)]
-#[allow(non_snake_case)]
-struct GoogleRequest {
-    passengers: Passengers,
-    slice: Vec<Slice>,
-    maxPrice: Option<String>,
-    saleCountry: Option<String>,
-    refundable: Option<bool>,
-    solutions: Option<u8>
-}
-
-#[derive(RustcEncodable)]
-#[allow(non_snake_case)]
-struct Passengers {
-    kind: &'static str,
-    adultCount: u8,
-    childCount: u8,
-    infantInLapCount: u8,
-    seniorCount: u8
+    pub provider: Option<String>,
+    pub provider_request_id: Option<String>,
+    pub sale_country: String,
+    pub passengers: Passengers,
+    pub max_price: Option<String>,
+    pub refundable: Option<bool>,
+    pub solutions: Option<u8>,
+    pub cache_hit: bool,
+    pub slices: Vec<Slice>
 }
 
-#[derive(RustcEncodable, Clone)]
-#[allow(non_snake_case)]
-struct Slice {
-    kind: &'static str,
-    origin: String,
-    destination: String,
-    date: String,
-    maxStops: u8,
-    maxConnectionDuration: Option<u32>,
-    preferredCabin: Option<String>
+#[derive(Clone)]
+pub struct Slice {
+    pub origin: String,
+    pub destination: String,
+    pub date: String,
+    pub max_stops: u8,
+    pub max_connection_duration: Option<u32>,
+    pub preferred_cabin: Option<String>,
+    pub permitted_connection_points: Option<Vec<String>>,
+    pub prohibited_connection_points: Option<Vec<String>>
 }
 
 impl Request {
-    pub fn new(name: &str, sale_country: &str) -> Self {
-        let passengers = Passengers {
-            kind: PASSENGER_COUNT_KIND,
-            adultCount: 1,
-            childCount: 0,
-            infantInLapCount: 0,
-            seniorCount: 0
-        };
-
-        let request = GoogleRequest {
-            passengers: passengers,
-            slice: vec!(),
-            maxPrice: None,
-            saleCountry: Some(sale_country.to_string()),
-            refundable: None,
-            solutions: None
-        };
-
-        let google_search_request = GoogleSearchRequest {
-            request: request
-        };
-
+    pub fn new(name: &str, sale_country: &str, passengers: &Passengers) -> Self {
         Request {
             id: None,
             name: name.to_string(),
             created_at: now_utc().to_timespec(),
-            google_search_request: Some(google_search_request)
+            provider: None,
+            provider_request_id: None,
+            sale_country: sale_country.to_string(),
+            passengers: passengers.clone(),
+            max_price: None,
+            refundable: None,
+            solutions: None,
+            cache_hit: false,
+            slices: vec!()
         }
     }
 
     pub fn add_trip(&mut self, origin: &str, destination: &str, date: &str, max_stops: u8) -> &mut Self {
         let slice = Slice {
-            kind: SLICE_KIND,
             origin: origin.to_string(),
             destination: destination.to_string(),
             date: date.to_string(),
-            maxStops: max_stops,
-            maxConnectionDuration: None,
-            preferredCabin: None
+            max_stops: max_stops,
+            max_connection_duration: None,
+            preferred_cabin: None,
+            permitted_connection_points: None,
+            prohibited_connection_points: None
         };
 
-        self.google_search_request.as_mut().unwrap().request.slice.push(slice);
+        self.slices.push(slice);
 
         self
     }
 
-    pub fn to_json(&self) -> Result<String, Error> {
-        json::encode(self.google_search_request.as_ref().unwrap()).map_err(|_| Error::EncodingJson )
+    pub fn max_price(&mut self, max_price: &str) -> &mut Self {
+        self.max_price = Some(max_price.to_string());
+
+        self
     }
 
-    pub fn call(&mut self, api_key: &str) -> Result<Vec<Offer>, Error> {
-        let url = SEARCH_URL.to_string() + "?key=" + api_key;
-        let request_body = try!(self.to_json());
+    pub fn refundable(&mut self, refundable: bool) -> &mut Self {
+        self.refundable = Some(refundable);
 
-        let client  = Client::new();
-        let request = client.post(Url(&url))
-            .header(Connection::close())
-            .header(ContentType::json())
-            .body(&request_body);
+        self
+    }
 
-        let mut response = try!(request.send().map_err(|_| Error::SendingRequest));
+    pub fn solutions(&mut self, solutions: u8) -> &mut Self {
+        self.solutions = Some(solutions);
 
-        let mut body = String::new();
-        try!(response.read_to_string(&mut body).map_err(|_| Error::ReadingResponse));
+        self
+    }
 
-        match response.status {
-            StatusCode::Ok => {
-                let conn = try!(Session::db_connection());
-                try!(self.create(&conn));
+    pub fn max_connection_duration(&mut self, minutes: u32) -> &mut Self {
+        self.last_slice().max_connection_duration = Some(minutes);
 
-                let request_id = try!(self.id.ok_or(Error::NoIdAssigned));
+        self
+    }
 
-                Offer::from_json(body, request_id)
-            },
-            _ => Err(Error::ResponseNotOk(response.status.to_string()))
-        }
+    pub fn preferred_cabin(&mut self, cabin: &str) -> &mut Self {
+        self.last_slice().preferred_cabin = Some(cabin.to_string());
+
+        self
+    }
+
+    pub fn permitted_connection_points(&mut self, airports: &[String]) -> &mut Self {
+        self.last_slice().permitted_connection_points = Some(airports.to_vec());
+
+        self
+    }
+
+    pub fn prohibited_connection_points(&mut self, airports: &[String]) -> &mut Self {
+        self.last_slice().prohibited_connection_points = Some(airports.to_vec());
+
+        self
+    }
+
+    fn last_slice(&mut self) -> &mut Slice {
+        self.slices.last_mut().unwrap()
+    }
+
+    /// The canonical form of a search, whichever provider answers it.
+    pub fn to_json(&self) -> Result<String, Error> {
+        qpx::encode(self)
+    }
+
+    pub fn search(self, provider: &FareProvider) -> Result<SearchResult, Error> {
+        let response = try!(self.fetch(provider));
+
+        SearchResult::decode(self, response, provider)
+    }
+
+    pub fn fetch(&self, provider: &FareProvider) -> Result<RawResponse, Error> {
+        let query = try!(provider.query(self));
+        let body = try!(provider.execute(&query));
+
+        Ok(RawResponse { query: query, body: body })
     }
 
     pub fn create(&mut self, conn: &DbConnection) -> Result<(), Error> {
@@ -145,28 +138,87 @@ impl Request {
             "INSERT INTO requests
                 (
                     name,
-                    created_at
-                ) VALUES (?, ?)"
+                    created_at,
+                    provider,
+                    adult_count,
+                    child_count,
+                    senior_count,
+                    infant_in_lap_count,
+                    infant_in_seat_count,
+                    max_price,
+                    refundable,
+                    solutions,
+                    cache_hit,
+                    provider_request_id
+                ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)"
             ).map_err(|err| Error::PreparingDbQuery(err.to_string())));
 
         try!(sql.execute(
             &[
                 &self.name,
-                &self.created_at
+                &self.created_at,
+                &self.provider,
+                &(self.passengers.adults as i64),
+                &(self.passengers.children as i64),
+                &(self.passengers.seniors as i64),
+                &(self.passengers.infants_in_lap as i64),
+                &(self.passengers.infants_in_seat as i64),
+                &self.max_price,
+                &self.refundable,
+                &self.solutions.map(|solutions| solutions as i64),
+                &self.cache_hit,
+                &self.provider_request_id
             ]).map_err(|err| Error::ExecutingDbQuery(err.to_string())));
 
-        self.id = Some(conn.last_insert_rowid());
+        let request_id = conn.last_insert_rowid();
+
+        for slice in &self.slices {
+            try!(slice.create(conn, request_id));
+        }
+
+        self.id = Some(request_id);
+
+        Ok(())
+    }
+
+    pub fn update_provider_request_id(conn: &DbConnection, id: i64, provider_request_id: &Option<String>) -> Result<(), Error> {
+        try!(conn.execute("UPDATE requests SET provider_request_id = ? WHERE id = ?", &[provider_request_id, &id])
+            .map_err(|err| Error::ExecutingDbQuery(err.to_string())));
 
         Ok(())
     }
 
     pub fn in_the_past_day(conn: &DbConnection) -> Result<Vec<Self>, Error> {
-        let mut sql = try!(conn.prepare(
-            "SELECT id, name, created_at FROM requests WHERE created_at > ?"
+        let one_day_ago = (now_utc() - Duration::hours(24)).to_timespec();
+
+        Request::load_where(conn, "created_at > ?", &one_day_ago)
+    }
+
+    pub fn find(conn: &DbConnection, id: i64) -> Result<Option<Self>, Error> {
+        Request::load_where(conn, "id = ?", &id).map(|requests| requests.into_iter().next())
+    }
+
+    fn load_where(conn: &DbConnection, condition: &str, value: &ToSql) -> Result<Vec<Self>, Error> {
+        let mut sql = try!(conn.prepare(&format!(
+            "SELECT
+                id,
+                name,
+                created_at,
+                provider,
+                adult_count,
+                child_count,
+                senior_count,
+                infant_in_lap_count,
+                infant_in_seat_count,
+                max_price,
+                refundable,
+                solutions,
+                cache_hit,
+                provider_request_id
+            FROM requests WHERE {}", condition)
             ).map_err(|err| Error::PreparingDbQuery(err.to_string())));
 
-        let one_day_ago = (now_utc() - Duration::hours(24)).to_timespec();
-        let rows = try!(sql.query(&[&one_day_ago]).map_err(|err| Error::ExecutingDbQuery(err.to_string())));
+        let rows = try!(sql.query(&[value]).map_err(|err| Error::ExecutingDbQuery(err.to_string())));
 
         let mut requests = Vec::new();
         for row in rows {
@@ -175,7 +227,21 @@ impl Request {
                 id: data.get(0),
                 name: data.get(1),
                 created_at: data.get(2),
-                google_search_request: None
+                provider: data.get(3),
+                provider_request_id: data.get(13),
+                sale_country: String::new(),
+                passengers: Passengers {
+                    adults: data.get::<i64>(4) as u8,
+                    children: data.get::<i64>(5) as u8,
+                    seniors: data.get::<i64>(6) as u8,
+                    infants_in_lap: data.get::<i64>(7) as u8,
+                    infants_in_seat: data.get::<i64>(8) as u8
+                },
+                max_price: data.get(9),
+                refundable: data.get(10),
+                solutions: data.get::<Option<i64>>(11).map(|solutions| solutions as u8),
+                cache_hit: data.get(12),
+                slices: vec!()
             };
 
             requests.push(request);
@@ -184,3 +250,37 @@ impl Request {
         Ok(requests)
     }
 }
+
+impl Slice {
+    fn create(&self, conn: &DbConnection, request_id: i64) -> Result<(), Error> {
+        let mut sql = try!(conn.prepare(
+            "INSERT INTO request_slices
+                (
+                    request_id,
+                    origin,
+                    destination,
+                    date,
+                    max_stops,
+                    max_connection_duration,
+                    preferred_cabin,
+                    permitted_connection_points,
+                    prohibited_connection_points
+                ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)"
+            ).map_err(|err| Error::PreparingDbQuery(err.to_string())));
+
+        try!(sql.execute(
+            &[
+                &request_id,
+                &self.origin,
+                &self.destination,
+                &self.date,
+                &(self.max_stops as i64),
+                &self.max_connection_duration.map(|minutes| minutes as i64),
+                &self.preferred_cabin,
+                &self.permitted_connection_points.as_ref().map(|airports| airports.join(",")),
+                &self.prohibited_connection_points.as_ref().map(|airports| airports.join(","))
+            ]).map_err(|err| Error::ExecutingDbQuery(err.to_string())));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,37 @@
+use flights::{Request, Offer};
+use flights::reference::ReferenceData;
+use providers::FareProvider;
+use Error;
+
+pub struct RawResponse {
+    pub query: String,
+    pub body: String
+}
+
+pub struct Fares {
+    pub provider_request_id: Option<String>,
+    pub offers: Vec<Offer>,
+    pub reference: ReferenceData
+}
+
+pub struct SearchResult {
+    pub request: Request,
+    pub response: RawResponse,
+    pub offers: Vec<Offer>,
+    pub reference: ReferenceData
+}
+
+impl SearchResult {
+    pub fn decode(mut request: Request, response: RawResponse, provider: &FareProvider) -> Result<Self, Error> {
+        let fares = try!(provider.decode(&response.body));
+        request.provider = Some(provider.name().to_string());
+        request.provider_request_id = fares.provider_request_id;
+
+        Ok(SearchResult {
+            request: request,
+            response: response,
+            offers: fares.offers,
+            reference: fares.reference
+        })
+    }
+}
@@ -0,0 +1,61 @@
+use rusqlite::Connection;
+
+use flights::{Request, Offer, Archive};
+use flights::search::{RawResponse, SearchResult, Fares};
+use flights::reference::ReferenceData;
+use Error;
+
+pub struct Store<'a> {
+    conn: &'a Connection,
+    archive: bool
+}
+
+impl<'a> Store<'a> {
+    pub fn new(conn: &'a Connection, archive: bool) -> Self {
+        Store { conn: conn, archive: archive }
+    }
+
+    pub fn save(&self, result: &mut SearchResult) -> Result<i64, Error> {
+        let provider = result.request.provider.clone().unwrap_or(String::new());
+        let request_id = try!(self.save_request(&mut result.request, &provider, &result.response));
+
+        try!(self.save_reference(&result.reference));
+        try!(self.save_offers(request_id, &mut result.offers));
+
+        Ok(request_id)
+    }
+
+    /// Called before decoding, so that archived responses survive decoding errors.
+    pub fn save_request(&self, request: &mut Request, provider: &str, response: &RawResponse) -> Result<i64, Error> {
+        request.provider = Some(provider.to_string());
+        try!(request.create(self.conn));
+
+        let request_id = try!(request.id.ok_or(Error::NoIdAssigned));
+
+        if self.archive && !request.cache_hit {
+            try!(Archive::new(request_id, provider, &response.query, &response.body).create(self.conn));
+        }
+
+        Ok(request_id)
+    }
+
+    pub fn save_fares(&self, request_id: i64, fares: &mut Fares) -> Result<(), Error> {
+        try!(Request::update_provider_request_id(self.conn, request_id, &fares.provider_request_id));
+        try!(self.save_reference(&fares.reference));
+
+        self.save_offers(request_id, &mut fares.offers)
+    }
+
+    pub fn save_reference(&self, reference: &ReferenceData) -> Result<(), Error> {
+        reference.create(self.conn)
+    }
+
+    pub fn save_offers(&self, request_id: i64, offers: &mut [Offer]) -> Result<(), Error> {
+        for offer in offers {
+            offer.request_id = Some(request_id);
+            try!(offer.create(self.conn));
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,175 @@
+use std::env;
+use std::io;
+use std::io::{Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::time::Duration;
+
+use time::{now_utc, strptime};
+use hyper;
+use hyper::{Client, Url};
+use hyper::client::{IntoUrl, Pool, RequestBuilder};
+use hyper::client::pool::Config as PoolConfig;
+use hyper::header::UserAgent;
+use hyper::net::{NetworkConnector, HttpStream, HttpsStream, Ssl, Openssl};
+
+use Error;
+
+const DEFAULT_CONNECT_TIMEOUT_MS: u64 = 10000;
+const DEFAULT_READ_TIMEOUT_MS: u64 = 30000;
+const DEFAULT_USER_AGENT: &'static str = concat!("flycheap/", env!("CARGO_PKG_VERSION"));
+const PROXY_ENV_VARS: [&'static str; 2] = ["HTTPS_PROXY", "https_proxy"];
+const MAX_PROXY_RESPONSE: usize = 8192;
+const HTTP_DATE_FORMAT: &'static str = "%a, %d %b %Y %H:%M:%S GMT";
+
+#[derive(RustcDecodable, PartialEq)]
+pub struct HttpConfig {
+    pub connect_timeout_ms: Option<u64>,
+    pub read_timeout_ms: Option<u64>,
+    pub proxy: Option<String>,
+    pub user_agent: Option<String>
+}
+
+pub struct HttpClient {
+    client: Client,
+    user_agent: String
+}
+
+struct Connector<S: Ssl> {
+    ssl: S,
+    connect_timeout: Duration,
+    proxy: Option<(String, u16)>
+}
+
+impl HttpClient {
+    pub fn new(config: Option<&HttpConfig>) -> Result<Self, Error> {
+        let connect_timeout_ms = config.and_then(|config| config.connect_timeout_ms).unwrap_or(DEFAULT_CONNECT_TIMEOUT_MS);
+        let read_timeout_ms = config.and_then(|config| config.read_timeout_ms).unwrap_or(DEFAULT_READ_TIMEOUT_MS);
+        let user_agent = config.and_then(|config| config.user_agent.clone()).unwrap_or(DEFAULT_USER_AGENT.to_string());
+
+        let proxy = match proxy(config) {
+            Some(proxy) => Some(try!(parse_proxy(&proxy))),
+            None => None
+        };
+
+        let connector = Connector {
+            ssl: Openssl::default(),
+            connect_timeout: Duration::from_millis(connect_timeout_ms),
+            proxy: proxy
+        };
+
+        let mut client = Client::with_connector(Pool::with_connector(PoolConfig::default(), connector));
+        client.set_read_timeout(Some(Duration::from_millis(read_timeout_ms)));
+        client.set_write_timeout(Some(Duration::from_millis(read_timeout_ms)));
+
+        Ok(HttpClient { client: client, user_agent: user_agent })
+    }
+
+    pub fn post<U: IntoUrl>(&self, url: U) -> RequestBuilder {
+        self.client.post(url).header(UserAgent(self.user_agent.clone()))
+    }
+}
+
+impl<S: Ssl> NetworkConnector for Connector<S> {
+    type Stream = HttpsStream<S::Stream>;
+
+    fn connect(&self, host: &str, port: u16, scheme: &str) -> hyper::Result<Self::Stream> {
+        let stream = match self.proxy {
+            Some((ref proxy_host, proxy_port)) => try!(self.tunnel(proxy_host, proxy_port, host, port)),
+            None => try!(self.tcp(host, port))
+        };
+
+        match scheme {
+            "https" => self.ssl.wrap_client(HttpStream(stream), host).map(HttpsStream::Https),
+            _ => Ok(HttpsStream::Http(HttpStream(stream)))
+        }
+    }
+}
+
+impl<S: Ssl> Connector<S> {
+    fn tcp(&self, host: &str, port: u16) -> io::Result<TcpStream> {
+        let mut last_error = io::Error::new(io::ErrorKind::NotFound, format!("{} has no address", host));
+
+        for addr in try!((host, port).to_socket_addrs()) {
+            match TcpStream::connect_timeout(&addr, self.connect_timeout) {
+                Ok(stream) => return Ok(stream),
+                Err(err) => last_error = err
+            }
+        }
+
+        Err(last_error)
+    }
+
+    fn tunnel(&self, proxy_host: &str, proxy_port: u16, host: &str, port: u16) -> io::Result<TcpStream> {
+        let mut stream = try!(self.tcp(proxy_host, proxy_port));
+        try!(stream.set_read_timeout(Some(self.connect_timeout)));
+        try!(write!(stream, "CONNECT {0}:{1} HTTP/1.1\r\nHost: {0}:{1}\r\n\r\n", host, port));
+
+        let mut response = Vec::new();
+        let mut byte = [0; 1];
+
+        while !response.ends_with(b"\r\n\r\n") {
+            if response.len() > MAX_PROXY_RESPONSE || try!(stream.read(&mut byte)) == 0 {
+                return Err(io::Error::new(io::ErrorKind::InvalidData, "the proxy closed the connection"))
+            }
+
+            response.push(byte[0]);
+        }
+
+        let response = String::from_utf8_lossy(&response);
+        let status_line = response.lines().next().unwrap_or("");
+
+        if status_line.split_whitespace().nth(1) != Some("200") {
+            return Err(io::Error::new(io::ErrorKind::Other, format!("the proxy refused to connect: {}", status_line)))
+        }
+
+        try!(stream.set_read_timeout(None));
+
+        Ok(stream)
+    }
+}
+
+fn proxy(config: Option<&HttpConfig>) -> Option<String> {
+    if let Some(proxy) = config.and_then(|config| config.proxy.clone()) {
+        return Some(proxy)
+    }
+
+    PROXY_ENV_VARS.iter().filter_map(|name| env::var(name).ok()).find(|proxy| !proxy.is_empty())
+}
+
+pub fn parse_proxy(proxy: &str) -> Result<(String, u16), Error> {
+    let url = if proxy.contains("://") { proxy.to_string() } else { format!("http://{}", proxy) };
+    let parsed = try!(Url::parse(&url).map_err(|err| Error::InvalidProxy(format!("{}: {}", proxy, err))));
+
+    match (parsed.serialize_host(), parsed.port_or_default()) {
+        (Some(host), Some(port)) => Ok((host, port)),
+        _ => Err(Error::InvalidProxy(format!("{} has no host and port", proxy)))
+    }
+}
+
+pub fn parse_retry_after(value: &str) -> Option<u64> {
+    let value = value.trim();
+    if let Ok(seconds) = value.parse() { return Some(seconds) }
+
+    strptime(value, HTTP_DATE_FORMAT).ok().map(|date| {
+        let seconds = date.to_timespec().sec - now_utc().to_timespec().sec;
+        if seconds > 0 { seconds as u64 } else { 0 }
+    })
+}
+
+pub fn sending_error(err: hyper::Error) -> Error {
+    match err {
+        hyper::Error::Io(ref err) if is_timeout(err) => Error::Timeout(err.to_string()),
+        err => Error::SendingRequest(err.to_string())
+    }
+}
+
+pub fn reading_error(err: io::Error) -> Error {
+    match is_timeout(&err) {
+        true => Error::Timeout(err.to_string()),
+        false => Error::ReadingResponse(err.to_string())
+    }
+}
+
+fn is_timeout(err: &io::Error) -> bool {
+    err.kind() == io::ErrorKind::TimedOut || err.kind() == io::ErrorKind::WouldBlock
+}
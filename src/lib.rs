@@ -8,16 +8,35 @@ extern crate mockito;
 
 pub mod session;
 pub mod flights;
+pub mod providers;
 pub mod money;
+pub mod dates;
+pub mod diagnostics;
+pub mod paths;
+pub mod secrets;
+pub mod reload;
+pub mod quota;
+pub mod http;
+pub mod executor;
+pub mod cache;
+pub mod watch;
 
 pub type Session = session::Session;
+pub type Watch = watch::Watch;
+
+use diagnostics::Diagnostic;
 
 #[derive(Debug)]
 pub enum Error {
-    LoadingConfig,
-    ReadingConfig,
-    ParsingConfig,
-    EstablishingDbConnection,
+    LoadingConfig(String),
+    ReadingConfig(String),
+    InvalidConfig(Vec<Diagnostic>),
+    InvalidPassengers(String),
+    ResolvingSecret(String),
+    UnknownProvider(String),
+    ReadingReplay(String),
+    InvalidDates(String),
+    EstablishingDbConnection(String),
     CreatingTransaction(String),
     CommitingTransaction(String),
     PreparingDbQuery(String),
@@ -25,9 +44,12 @@ pub enum Error {
     UnwrappingDbRow(String),
     NoIdAssigned,
     EncodingJson,
-    SendingRequest,
-    ReadingResponse,
-    ResponseNotOk(String),
+    InvalidProxy(String),
+    SendingRequest(String),
+    Timeout(String),
+    ReadingResponse(String),
+    ResponseNotOk { status: u16, body: String, retry_after: Option<u64> },
+    QuotaUsedUp,
     DecodingJson(String),
     NoPricing,
     NoFlights,
@@ -1,45 +1,416 @@
 extern crate flycheap;
+extern crate rusqlite;
+extern crate time;
 
-use std::thread::sleep;
+use std::env;
+use std::process::exit;
+use std::time::Duration;
 
-use flycheap::Session;
+use rusqlite::Connection;
+
+use flycheap::{Session, Watch, Error};
+use flycheap::flights::{Archive, Offer, Request, Store};
+use flycheap::flights::search::RawResponse;
+use flycheap::providers::FareProvider;
+use flycheap::cache::Cache;
+use flycheap::paths;
+use flycheap::paths::Location;
+use flycheap::reload::ConfigWatcher;
+use flycheap::providers::Providers;
+use flycheap::quota;
+use flycheap::executor;
+
+const USAGE: &'static str = "usage: flycheap [check-config | reparse [REQUEST_ID] | offer PROVIDER_ID] [--config PATH] [--db PATH] [--dry-run [--runs N]]";
+const DEFAULT_DRY_RUNS: usize = 5;
+const IDLE_WAIT_SECONDS: u64 = 3600;
+
+struct Options {
+    command: Option<String>,
+    arguments: Vec<String>,
+    config: Location,
+    db: Location,
+    dry_run: bool,
+    runs: usize
+}
 
 fn main() {
-    let session = Session::load().unwrap();
-    let conn = Session::db_connection().unwrap();
-    session.db_setup(&conn).unwrap();
+    let options = match parse_options(env::args().skip(1).collect()) {
+        Ok(options) => options,
+        Err(message) => {
+            println!("{}\n{}", message, USAGE);
+            exit(2);
+        }
+    };
+
+    match options.command {
+        None if options.dry_run => dry_run(&options),
+        None => run(&options),
+        Some(ref command) if command == "check-config" => check_config(&options),
+        Some(ref command) if command == "reparse" => reparse(&options),
+        Some(ref command) if command == "offer" => offer(&options),
+        Some(ref command) => {
+            println!("unknown command {}\n{}", command, USAGE);
+            exit(2);
+        }
+    }
+}
+
+fn parse_options(args: Vec<String>) -> Result<Options, String> {
+    let mut command = None;
+    let mut arguments = Vec::new();
+    let mut config = None;
+    let mut db = None;
+    let mut dry_run = false;
+    let mut runs = DEFAULT_DRY_RUNS;
+    let mut args = args.into_iter();
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--config" => config = Some(try!(args.next().ok_or("--config needs a path".to_string()))),
+            "--db" => db = Some(try!(args.next().ok_or("--db needs a path".to_string()))),
+            "--dry-run" => dry_run = true,
+            "--runs" => runs = try!(args.next().and_then(|runs| runs.parse().ok()).ok_or("--runs needs a number".to_string())),
+            _ if command.is_none() && !arg.starts_with("--") => command = Some(arg),
+            _ if !arg.starts_with("--") => arguments.push(arg),
+            _ => return Err(format!("unexpected argument {}", arg))
+        }
+    }
+
+    Ok(Options {
+        command: command,
+        arguments: arguments,
+        dry_run: dry_run,
+        runs: runs,
+        config: paths::config(config.as_ref().map(|path| path.as_str())),
+        db: paths::db(db.as_ref().map(|path| path.as_str()))
+    })
+}
+
+fn check_config(options: &Options) {
+    println!("config: {}", options.config);
+
+    checked_session(options);
+    println!("config is valid");
+}
+
+fn checked_session(options: &Options) -> Session {
+    let source = match Session::read_config(&options.config) {
+        Ok(source) => source,
+        Err(error) => {
+            println!("{:?}", error);
+            exit(1);
+        }
+    };
+
+    let (session, diagnostics) = Session::check(&source);
+
+    for diagnostic in &diagnostics {
+        println!("{}", diagnostic);
+    }
+
+    match session {
+        Some(session) if !diagnostics.iter().any(|diagnostic| diagnostic.is_error()) => session,
+        _ => exit(1)
+    }
+}
+
+fn reparse(options: &Options) {
+    let request_id = match options.arguments.first().map(|id| id.parse::<i64>()) {
+        None => None,
+        Some(Ok(id)) => Some(id),
+        Some(Err(_)) => {
+            println!("REQUEST_ID has to be a number\n{}", USAGE);
+            exit(2);
+        }
+    };
+
+    let loaded = Session::db_connection(&options.db).and_then(|conn| {
+        try!(Session::db_setup(&conn));
+        let archives = try!(Archive::load(&conn, request_id));
+        Ok((conn, archives))
+    });
+
+    let (conn, archives) = match loaded {
+        Ok(loaded) => loaded,
+        Err(error) => {
+            println!("an error occured: {:?}", error);
+            exit(1);
+        }
+    };
+
+    if archives.is_empty() {
+        println!("no archived responses found");
+        exit(1);
+    }
+
+    for archive in &archives {
+        println!("reparsing request {} ({})...\n", archive.request_id, archive.provider);
+
+        match archive.reparse(&conn) {
+            Ok(offers) => {
+                for offer in &offers {
+                    println!("{}", offer);
+                }
+            },
+            Err(error) => println!("an error occured: {:?}\n", error)
+        }
+    }
+}
+
+fn offer(options: &Options) {
+    let provider_id = match options.arguments.first() {
+        Some(id) => id,
+        None => {
+            println!("offer needs a PROVIDER_ID\n{}", USAGE);
+            exit(2);
+        }
+    };
+
+    let offers = Session::db_connection(&options.db).and_then(|conn| {
+        try!(Session::db_setup(&conn));
+        print_offers_by_provider_id(&conn, provider_id)
+    });
+
+    match offers {
+        Ok(0) => {
+            println!("no such offer: {}", provider_id);
+            exit(1);
+        },
+        Ok(_) => {},
+        Err(error) => {
+            println!("an error occured: {:?}", error);
+            exit(1);
+        }
+    }
+}
+
+fn print_offers_by_provider_id(conn: &Connection, provider_id: &str) -> Result<usize, Error> {
+    let offers = try!(Offer::find_by_provider_id(conn, provider_id));
+
+    for offer in &offers {
+        let request_id = try!(offer.request_id.ok_or(Error::NoIdAssigned));
+        let archived = !try!(Archive::load(conn, Some(request_id))).is_empty();
+
+        println!("offer {} of request {}", offer.id.unwrap_or(0), request_id);
+
+        if let Some(request) = try!(Request::find(conn, request_id)) {
+            println!("watch: {}", request.name);
+            println!("searched at: {}", time::at_utc(request.created_at).rfc822());
+            println!("provider: {}", request.provider.unwrap_or(String::new()));
+            println!("provider request id: {}", request.provider_request_id.unwrap_or(String::new()));
+            println!("from cache: {}", request.cache_hit);
+        }
+
+        println!("archived response: {}\n", archived);
+        println!("{}", offer);
+
+        for pricing in &offer.pricings {
+            println!("{} fare calculation: {}", pricing.passenger_type, pricing.fare_calculation);
+        }
+
+        for tax in &offer.taxes {
+            println!("{} tax {}: {}{}", tax.passenger_type, tax.name.as_ref().unwrap_or(&tax.code), tax.amount, tax.currency);
+        }
+
+        println!("");
+    }
+
+    Ok(offers.len())
+}
+
+fn dry_run(options: &Options) {
+    println!("config: {}", options.config);
+
+    let session = checked_session(options);
+    println!("total calls per request set: {}\n", session.total_calls());
+
+    for watch in &session.watches {
+        println!("watch {} ({})", watch.name, watch.provider());
+        println!("total calls: {}", watch.total_calls());
+        println!("request sets per day: {}", session.request_sets_per_day(watch));
+
+        match session.next_runs_at(watch, options.runs) {
+            Ok(runs) => {
+                println!("duration per request: {} seconds", session.duration_per_request(watch).unwrap().num_seconds());
+
+                for run_at in runs {
+                    println!("next run at: {}", run_at.rfc822());
+                }
+            },
+            Err(_) => println!("never runs, its budget doesn't cover a single request set")
+        }
+
+        for request in session.requests(watch) {
+            println!("{}", request.to_json().unwrap());
+        }
+
+        println!("");
+    }
+}
+
+fn run(options: &Options) {
+    println!("config: {}", options.config);
+    println!("database: {}", options.db);
+
+    let mut session = Session::load(&options.config).unwrap();
+    let mut providers = Providers::from_session(&session).unwrap();
+    let mut watcher = ConfigWatcher::new(&options.config);
+    let conn = Session::db_connection(&options.db).unwrap();
+    Session::db_setup(&conn).unwrap();
+
+    print_idle_watches(&session);
 
     loop {
-        let next_run = session.next_run_duration().unwrap();
+        let next = session.next_watches().and_then(|watches| {
+            let names: Vec<String> = watches.iter().map(|watch| watch.name.clone()).collect();
+
+            session.next_run_duration(watches[0]).map(|next_run| (next_run, names))
+        });
+
+        let (next_run, due) = match next {
+            Ok(next) => next,
+            Err(_) => {
+                println!("no watch can run, waiting for the config to change");
+
+                if watcher.wait(Duration::from_secs(IDLE_WAIT_SECONDS)) {
+                    reload(&mut session, &mut providers, &options.config);
+                }
+
+                continue;
+            }
+        };
+
         println!("next run in {} seconds", next_run.as_secs());
 
-        sleep(next_run);
+        if watcher.wait(next_run) {
+            reload(&mut session, &mut providers, &options.config);
+            continue;
+        }
+
+        for watch in session.watches.iter().filter(|watch| due.contains(&watch.name)) {
+            run_watch(&session, &providers, &conn, watch);
+        }
 
-        println!("requesting offers...\n");
-        for mut request in session.requests() {
-            match request.call(&session.google_api_key) {
-                Ok(mut offers) => {
-                    for offer in &mut offers {
-                        println!("{}", offer);
-                        let _ = offer.create(&conn);
-                    }
-                },
-                Err(error) => println!("an error occured: {:?}\n", error)
-            };
+        match quota::calls_today(&conn) {
+            Ok(calls) => println!("{} of {} calls made today", calls, session.requests_per_day),
+            Err(error) => println!("an error occured: {:?}\n", error)
         }
     }
 
     // TODO: if any price < total average => deliver report (mailgun?)
 
-    // TODO: count dates, not trips
-
-    // TODO: save carrier in offers table (if always the same)
-
     // TODO: daily / weekly report
 
-    // TODO: save config (?) & db to home folder
-
     // TODO: remove all unwrap calls + handle offer errros gracefully (?)
 
     // TODO: extract time functions to their own module
 }
+
+fn run_watch(session: &Session, providers: &Providers, conn: &Connection, watch: &Watch) {
+    println!("requesting offers for {}...\n", watch.name);
+    let provider = match providers.shared(watch.provider()) {
+        Ok(provider) => provider,
+        Err(error) => return println!("an error occured: {:?}\n", error)
+    };
+    let cache = session.cache();
+    let store = Store::new(conn, session.archive());
+
+    let mut requests = vec!();
+    for mut request in session.requests(watch) {
+        let cached = Cache::key(provider.name(), &request).and_then(|key| cache.get(conn, &key));
+
+        match cached {
+            Ok(Some(response)) => {
+                request.cache_hit = true;
+                print_offers(save(&store, &*provider, request, &response));
+            },
+            Ok(None) => requests.push(request),
+            Err(error) => {
+                println!("cache lookup failed, searching again: {:?}\n", error);
+                requests.push(request);
+            }
+        }
+    }
+
+    let remaining = match quota::remaining(conn, session.requests_per_day) {
+        Ok(remaining) => remaining,
+        Err(error) => {
+            println!("can't tell how many calls are left today, skipping {} searches: {:?}\n", requests.len(), error);
+            return;
+        }
+    };
+
+    if requests.len() > remaining {
+        println!("daily budget of {} calls used up, skipping {} searches until {}\n",
+            session.requests_per_day, requests.len() - remaining, quota::next_reset().rfc822());
+        requests.truncate(remaining);
+    }
+
+    providers.allow(remaining);
+
+    for (request, result) in executor::execute(requests, provider.clone(), session.concurrency()) {
+        for mut call in providers.take_calls() {
+            if let Err(error) = call.create(conn) {
+                println!("an error occured: {:?}\n", error);
+            }
+        }
+
+        let result = result.and_then(|response| {
+            let key = try!(Cache::key(provider.name(), &request));
+            let offers = try!(save(&store, &*provider, request, &response));
+            try!(cache.put(conn, &key, &response));
+
+            Ok(offers)
+        });
+
+        print_offers(result);
+    }
+}
+
+fn save(store: &Store, provider: &FareProvider, mut request: Request, response: &RawResponse) -> Result<Vec<Offer>, Error> {
+    let request_id = try!(store.save_request(&mut request, provider.name(), response));
+    let mut fares = try!(provider.decode(&response.body));
+
+    try!(store.save_fares(request_id, &mut fares));
+
+    Ok(fares.offers)
+}
+
+fn print_offers(result: Result<Vec<Offer>, Error>) {
+    match result {
+        Ok(offers) => {
+            for offer in &offers {
+                println!("{}", offer);
+            }
+        },
+        Err(error) => println!("an error occured: {:?}\n", error)
+    };
+}
+
+fn print_idle_watches(session: &Session) {
+    for watch in session.idle_watches() {
+        println!("skipping watch {}, it has no dates left or its budget can't cover its {} searches", watch.name, watch.total_calls());
+    }
+}
+
+fn reload(session: &mut Session, providers: &mut Providers, location: &Location) {
+    println!("config changed, reloading {}", location);
+
+    let loaded = Session::load(location).and_then(|new_session| {
+        Providers::from_session(&new_session).map(|new_providers| (new_session, new_providers))
+    });
+
+    match loaded {
+        Ok((new_session, new_providers)) => {
+            for change in session.diff(&new_session) {
+                println!("{}", change);
+            }
+
+            *session = new_session;
+            *providers = new_providers;
+
+            print_idle_watches(session);
+        },
+        Err(error) => println!("keeping the current config, the new one is invalid: {:?}\n", error)
+    }
+}
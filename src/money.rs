@@ -17,3 +17,7 @@ pub fn parse(input: &str) -> Result<(f64, &str), Error> {
         _ => Err(Error::ParsingMoney(input.to_string()))
     }
 }
+
+pub fn round(amount: f64) -> f64 {
+    (amount * 100.0).round() / 100.0
+}
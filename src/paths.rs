@@ -0,0 +1,73 @@
+use std::env;
+use std::fmt::{Display, Formatter};
+use std::fmt::Result as FmtResult;
+use std::path::PathBuf;
+
+const CONFIG_ENV: &'static str = "FLYCHEAP_CONFIG";
+const DB_ENV: &'static str = "FLYCHEAP_DB";
+const CONFIG_FILE: &'static str = "config.toml";
+const DB_FILE: &'static str = "data.sqlite";
+const APP_DIR: &'static str = "flycheap";
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Source {
+    Flag(&'static str),
+    Environment(&'static str),
+    Xdg(&'static str),
+    Home(&'static str),
+    WorkingDirectory
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Location {
+    pub path: PathBuf,
+    pub source: Source
+}
+
+/// `--config`, `FLYCHEAP_CONFIG`, `$XDG_CONFIG_HOME` or `~/.config`, in this order.
+pub fn config(flag: Option<&str>) -> Location {
+    resolve(flag, "--config", CONFIG_ENV, "XDG_CONFIG_HOME", ".config", CONFIG_FILE)
+}
+
+/// `--db`, `FLYCHEAP_DB`, `$XDG_DATA_HOME` or `~/.local/share`, in this order.
+pub fn db(flag: Option<&str>) -> Location {
+    resolve(flag, "--db", DB_ENV, "XDG_DATA_HOME", ".local/share", DB_FILE)
+}
+
+fn resolve(flag: Option<&str>, flag_name: &'static str, env_name: &'static str, xdg_name: &'static str, home_dir: &'static str, file: &str) -> Location {
+    if let Some(path) = flag {
+        return Location { path: PathBuf::from(path), source: Source::Flag(flag_name) }
+    }
+
+    if let Some(path) = non_empty_var(env_name) {
+        return Location { path: PathBuf::from(path), source: Source::Environment(env_name) }
+    }
+
+    if let Some(path) = non_empty_var(xdg_name) {
+        return Location { path: PathBuf::from(path).join(APP_DIR).join(file), source: Source::Xdg(xdg_name) }
+    }
+
+    if let Some(path) = non_empty_var("HOME") {
+        return Location { path: PathBuf::from(path).join(home_dir).join(APP_DIR).join(file), source: Source::Home(home_dir) }
+    }
+
+    Location { path: PathBuf::from(file), source: Source::WorkingDirectory }
+}
+
+fn non_empty_var(name: &str) -> Option<String> {
+    env::var(name).ok().and_then(|value| if value.is_empty() { None } else { Some(value) })
+}
+
+impl Display for Location {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        try!(write!(f, "{} ", self.path.display()));
+
+        match self.source {
+            Source::Flag(name) => write!(f, "(from {})", name),
+            Source::Environment(name) => write!(f, "(from ${})", name),
+            Source::Xdg(name) => write!(f, "(from the {} directory)", name),
+            Source::Home(dir) => write!(f, "(from ~/{})", dir),
+            Source::WorkingDirectory => write!(f, "(from the working directory)")
+        }
+    }
+}
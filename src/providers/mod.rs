@@ -0,0 +1,100 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use flights::Request;
+use flights::search::Fares;
+use quota::{Allowance, Call};
+use http::HttpClient;
+use Session;
+use Error;
+
+pub mod qpx;
+pub mod replay;
+pub mod retry;
+pub mod throttle;
+
+pub const DEFAULT_PROVIDER: &'static str = "qpx";
+pub const PROVIDERS: [&'static str; 2] = ["qpx", "replay"];
+
+const EXCERPT_LENGTH: usize = 200;
+
+/// Searches are built, executed and decoded in separate steps so that raw responses can be kept.
+pub trait FareProvider: Send + Sync {
+    fn name(&self) -> &str;
+
+    fn query(&self, request: &Request) -> Result<String, Error>;
+
+    fn execute(&self, query: &str) -> Result<String, Error>;
+
+    fn decode(&self, body: &str) -> Result<Fares, Error>;
+}
+
+pub struct Providers {
+    providers: HashMap<String, Arc<retry::Retrying>>,
+    allowance: Arc<Allowance>
+}
+
+impl Providers {
+    pub fn from_session(session: &Session) -> Result<Self, Error> {
+        let mut providers = HashMap::new();
+        let client = Arc::new(try!(HttpClient::new(session.http.as_ref())));
+        let allowance = Arc::new(Allowance::new());
+
+        for watch in &session.watches {
+            let name = watch.provider();
+            if providers.contains_key(name) { continue }
+
+            let provider: Box<FareProvider> = match name {
+                "qpx" => Box::new(qpx::Qpx::new(try!(session.google_api_key()), client.clone())),
+                "replay" => match session.replay {
+                    Some(ref config) => Box::new(replay::Replay::new(config)),
+                    None => return Err(Error::ReadingReplay("the replay provider needs a [replay] table".to_string()))
+                },
+                _ => return Err(Error::UnknownProvider(name.to_string()))
+            };
+
+            let policy = retry::RetryPolicy::new(session.retry.as_ref());
+            let mut retrying = retry::Retrying::new(provider, policy).limited(allowance.clone());
+
+            if let Some(calls_per_second) = session.calls_per_second(name) {
+                retrying = retrying.throttled(throttle::Throttle::new(calls_per_second));
+            }
+
+            providers.insert(name.to_string(), Arc::new(retrying));
+        }
+
+        Ok(Providers { providers: providers, allowance: allowance })
+    }
+
+    pub fn get(&self, name: &str) -> Result<&FareProvider, Error> {
+        self.providers.get(name).map(|provider| &**provider as &FareProvider).ok_or(Error::UnknownProvider(name.to_string()))
+    }
+
+    pub fn shared(&self, name: &str) -> Result<Arc<FareProvider>, Error> {
+        self.providers.get(name).map(|provider| provider.clone() as Arc<FareProvider>).ok_or(Error::UnknownProvider(name.to_string()))
+    }
+
+    /// Limits the calls all providers may make from now on, retries included.
+    pub fn allow(&self, calls: usize) {
+        self.allowance.set(calls);
+    }
+
+    pub fn take_calls(&self) -> Vec<Call> {
+        self.providers.values().flat_map(|provider| provider.take_calls()).collect()
+    }
+}
+
+/// Decodes a response of the named provider, without it having to be configured.
+pub fn decode(provider: &str, body: &str) -> Result<Fares, Error> {
+    match provider {
+        "qpx" | "replay" => qpx::decode(body),
+        _ => Err(Error::UnknownProvider(provider.to_string()))
+    }
+}
+
+pub fn excerpt(body: &str) -> String {
+    match body.char_indices().nth(EXCERPT_LENGTH) {
+        Some((end, _)) => format!("{}...", &body[..end]),
+        None => body.to_string()
+    }
+}
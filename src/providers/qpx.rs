@@ -0,0 +1,498 @@
+use std::io::Read;
+use std::cmp::min;
+use std::sync::Arc;
+
+use rustc_serialize::json;
+use hyper::header::ContentType;
+use hyper::status::StatusCode;
+use mockito::url::Url;
+use time::{strptime, Tm};
+
+use flights::{Request, Offer, OfferTax};
+use flights::offer;
+use flights::reference;
+use flights::reference::ReferenceData;
+use flights::search::Fares;
+use providers::{excerpt, FareProvider};
+use secrets::Secret;
+use http::{HttpClient, sending_error, reading_error, parse_retry_after};
+use money;
+use Error;
+
+const SEARCH_URL: &'static str = "https://www.googleapis.com/qpxExpress/v1/trips/search";
+const PASSENGER_COUNT_KIND: &'static str = "qpxexpress#passengerCounts";
+const SLICE_KIND: &'static str = "qpxexpress#sliceInput";
+const ISO_TIME_FORMAT: &'static str = "%Y-%m-%dT%H:%M%z";
+
+pub struct Qpx {
+    api_key: Secret,
+    client: Arc<HttpClient>
+}
+
+#[derive(RustcEncodable)]
+struct GoogleSearchRequest {
+    request: GoogleRequest
+}
+
+#[derive(RustcEncodable)]
+#[allow(non_snake_case)]
+struct GoogleRequest {
+    passengers: Passengers,
+    slice: Vec<SliceInput>,
+    maxPrice: Option<String>,
+    saleCountry: Option<String>,
+    refundable: Option<bool>,
+    solutions: Option<u8>
+}
+
+#[derive(RustcEncodable)]
+#[allow(non_snake_case)]
+struct Passengers {
+    kind: &'static str,
+    adultCount: u8,
+    childCount: u8,
+    infantInLapCount: u8,
+    infantInSeatCount: u8,
+    seniorCount: u8
+}
+
+#[derive(RustcEncodable)]
+#[allow(non_snake_case)]
+struct SliceInput {
+    kind: &'static str,
+    origin: String,
+    destination: String,
+    date: String,
+    maxStops: u8,
+    maxConnectionDuration: Option<u32>,
+    preferredCabin: Option<String>,
+    permittedConnectionPoint: Option<Vec<String>>,
+    prohibitedConnectionPoint: Option<Vec<String>>
+}
+
+#[derive(RustcDecodable)]
+struct SearchResponse {
+    trips: Trips
+}
+
+impl SearchResponse {
+    fn to_fares(self) -> Result<Fares, Error> {
+        let mut offers = vec!();
+        for option in self.trips.tripOption {
+            match option.to_offer() {
+                Ok(offer) => offers.push(offer),
+                _ => {}
+            };
+        }
+
+        let reference = self.trips.data.to_reference_data();
+        reference.annotate(&mut offers);
+
+        Ok(Fares { provider_request_id: Some(self.trips.requestId), offers: offers, reference: reference })
+    }
+}
+
+#[derive(RustcDecodable)]
+#[allow(non_snake_case)]
+struct Trips {
+    requestId: String,
+    data: TripsData,
+    tripOption: Vec<TripOption>
+}
+
+#[derive(RustcDecodable)]
+struct TripsData {
+    airport: Vec<Airport>,
+    city: Vec<City>,
+    aircraft: Vec<Aircraft>,
+    tax: Vec<Tax>,
+    carrier: Vec<Carrier>
+}
+
+impl TripsData {
+    fn to_reference_data(self) -> ReferenceData {
+        ReferenceData {
+            airports: self.airport.into_iter().map(|airport| reference::Airport { code: airport.code, city: airport.city, name: airport.name }).collect(),
+            cities: self.city.into_iter().map(|city| reference::City { code: city.code, name: city.name }).collect(),
+            carriers: self.carrier.into_iter().map(|carrier| reference::Carrier { code: carrier.code, name: carrier.name }).collect(),
+            aircraft: self.aircraft.into_iter().map(|aircraft| reference::Aircraft { code: aircraft.code, name: aircraft.name }).collect(),
+            taxes: self.tax.into_iter().map(|tax| reference::Tax { id: tax.id, name: tax.name }).collect()
+        }
+    }
+}
+
+#[derive(RustcDecodable)]
+struct Airport {
+    code: String,
+    city: String,
+    name: String
+}
+
+#[derive(RustcDecodable)]
+struct City {
+    code: String,
+    name: String
+}
+
+#[derive(RustcDecodable)]
+struct Aircraft {
+    code: String,
+    name: String
+}
+
+#[derive(RustcDecodable)]
+struct Tax {
+    id: String,
+    name: String
+}
+
+#[derive(RustcDecodable)]
+struct Carrier {
+    code: String,
+    name: String
+}
+
+#[derive(RustcDecodable)]
+#[allow(non_snake_case)]
+struct TripOption {
+    saleTotal: String,
+    id: String,
+    slice: Vec<Slice>,
+    pricing: Vec<Pricing>
+}
+
+#[derive(RustcDecodable)]
+struct Slice {
+    duration: i64,
+    segment: Vec<Segment>
+}
+
+#[derive(RustcDecodable)]
+#[allow(non_snake_case)]
+struct Segment {
+    duration: i64,
+    flight: GoogleFlight,
+    id: String,
+    cabin: String,
+    bookingCode: String,
+    bookingCodeCount: i64,
+    leg: Vec<Leg>,
+    connectionDuration: Option<i64>
+}
+
+#[derive(RustcDecodable)]
+#[allow(non_snake_case)]
+struct Leg {
+    id: String,
+    aircraft: String,
+    arrivalTime: String,
+    departureTime: String,
+    origin: String,
+    destination: String,
+    duration: i64,
+    mileage: i64,
+    meal: Option<String>
+}
+
+#[derive(RustcDecodable)]
+struct GoogleFlight {
+    carrier: String,
+    number: String
+}
+
+#[derive(RustcDecodable)]
+#[allow(non_snake_case)]
+struct Pricing {
+    fare: Vec<FareInfo>,
+    baseFareTotal: String,
+    saleFareTotal: String,
+    saleTaxTotal: String,
+    saleTotal: String,
+    passengers: PassengerCounts,
+    fareCalculation: String,
+    latestTicketingTime: String,
+    ptc: String,
+    refundable: Option<bool>,
+    tax: Option<Vec<TaxInfo>>
+}
+
+#[derive(RustcDecodable)]
+#[allow(non_snake_case)]
+struct FareInfo {
+    carrier: String,
+    origin: String,
+    destination: String,
+    basisCode: String
+}
+
+#[derive(RustcDecodable)]
+#[allow(non_snake_case)]
+struct PassengerCounts {
+    adultCount: Option<i64>,
+    childCount: Option<i64>,
+    infantInLapCount: Option<i64>,
+    infantInSeatCount: Option<i64>,
+    seniorCount: Option<i64>
+}
+
+impl PassengerCounts {
+    fn total(&self) -> i64 {
+        [self.adultCount, self.childCount, self.infantInLapCount, self.infantInSeatCount, self.seniorCount]
+            .iter().fold(0, |acc, count| acc + count.unwrap_or(0))
+    }
+}
+
+impl Pricing {
+    fn to_pricing(&self) -> Result<offer::Pricing, Error> {
+        let (base_price, _) = try!(money::parse(&self.baseFareTotal));
+        let (sale_price, _) = try!(money::parse(&self.saleFareTotal));
+        let (tax_price, _) = try!(money::parse(&self.saleTaxTotal));
+        let (total_price, currency) = try!(money::parse(&self.saleTotal));
+
+        let fares = self.fare.iter().map(|fare| {
+            offer::Fare {
+                id: None,
+                pricing_id: None,
+                carrier: fare.carrier.clone(),
+                origin: fare.origin.clone(),
+                destination: fare.destination.clone(),
+                basis_code: fare.basisCode.clone()
+            }
+        }).collect();
+
+        Ok(offer::Pricing {
+            id: None,
+            offer_id: None,
+            passenger_type: self.ptc.clone(),
+            passengers: self.passengers.total(),
+            currency: currency.to_string(),
+            base_price: base_price,
+            sale_price: sale_price,
+            tax_price: tax_price,
+            total_price: total_price,
+            fare_calculation: self.fareCalculation.clone(),
+            fares: fares
+        })
+    }
+}
+
+#[derive(RustcDecodable)]
+#[allow(non_snake_case)]
+struct TaxInfo {
+    id: String,
+    chargeType: Option<String>,
+    code: String,
+    country: Option<String>,
+    salePrice: String
+}
+
+impl TripOption {
+    fn to_offer(self) -> Result<Offer, Error> {
+        let mut slices = vec!();
+        let mut stops = 0;
+
+        for slice in self.slice {
+            let legs = slice.segment.iter().fold(0, |acc, segment| acc + segment.leg.len() as i64);
+            if legs > 0 { stops += legs - 1; }
+
+            let mut segments = vec!();
+
+            for segment in slice.segment {
+                let mut legs = vec!();
+
+                for leg in segment.leg {
+                    let departs_at = try!(parse_time(leg.departureTime));
+                    let arrives_at = try!(parse_time(leg.arrivalTime));
+
+                    legs.push(offer::Leg {
+                        id: None,
+                        segment_id: None,
+                        origin: leg.origin,
+                        destination: leg.destination,
+                        departs_at: departs_at.to_timespec(),
+                        departs_at_offset: departs_at.tm_utcoff as i64,
+                        arrives_at: arrives_at.to_timespec(),
+                        arrives_at_offset: arrives_at.tm_utcoff as i64,
+                        duration: leg.duration,
+                        mileage: leg.mileage,
+                        aircraft: leg.aircraft,
+                        meal: leg.meal,
+                        origin_name: None,
+                        destination_name: None,
+                        aircraft_name: None
+                    });
+                }
+
+                segments.push(offer::Segment {
+                    id: None,
+                    slice_id: None,
+                    carrier: segment.flight.carrier,
+                    number: segment.flight.number,
+                    cabin: segment.cabin,
+                    booking_code: segment.bookingCode,
+                    booking_code_count: segment.bookingCodeCount,
+                    duration: segment.duration,
+                    connection_duration: segment.connectionDuration,
+                    carrier_name: None,
+                    legs: legs
+                });
+            }
+
+            slices.push(offer::Slice {
+                id: None,
+                offer_id: None,
+                duration: slice.duration,
+                segments: segments
+            });
+        }
+
+        let mut pricings = vec!();
+        let mut taxes = vec!();
+        let mut latest_ticketing_at = None;
+
+        for pricing in &self.pricing {
+            let ticketing_at = try!(parse_time(pricing.latestTicketingTime.clone())).to_timespec();
+            latest_ticketing_at = Some(latest_ticketing_at.map_or(ticketing_at, |at| min(at, ticketing_at)));
+
+            taxes.extend(try!(to_taxes(&pricing.ptc, &pricing.tax)));
+            pricings.push(try!(pricing.to_pricing()));
+        }
+
+        let (total_price, currency) = try!(money::parse(&self.saleTotal));
+        let sum = |price: fn(&offer::Pricing) -> f64| money::round(pricings.iter().fold(0.0, |acc, pricing| acc + price(pricing) * pricing.passengers as f64));
+
+        let offer = Offer {
+            id: None,
+            request_id: None,
+            provider_id: Some(self.id.clone()),
+            currency: currency.to_string(),
+            base_price: sum(|pricing| pricing.base_price),
+            sale_price: sum(|pricing| pricing.sale_price),
+            tax_price: sum(|pricing| pricing.tax_price),
+            total_price: total_price,
+            latest_ticketing_at: try!(latest_ticketing_at.ok_or(Error::NoPricing)),
+            refundable: self.pricing.iter().all(|pricing| pricing.refundable.unwrap_or(false)),
+            stops: stops,
+            slices: slices,
+            pricings: pricings,
+            taxes: taxes
+        };
+
+        Ok(offer)
+    }
+}
+
+impl Qpx {
+    pub fn new(api_key: Secret, client: Arc<HttpClient>) -> Self {
+        Qpx { api_key: api_key, client: client }
+    }
+}
+
+impl FareProvider for Qpx {
+    fn name(&self) -> &str {
+        "qpx"
+    }
+
+    fn query(&self, request: &Request) -> Result<String, Error> {
+        encode(request)
+    }
+
+    fn execute(&self, query: &str) -> Result<String, Error> {
+        let url = SEARCH_URL.to_string() + "?key=" + self.api_key.expose();
+
+        let request = self.client.post(Url(&url))
+            .header(ContentType::json())
+            .body(query);
+
+        let mut response = try!(request.send().map_err(sending_error));
+
+        let mut body = String::new();
+        try!(response.read_to_string(&mut body).map_err(reading_error));
+
+        match response.status {
+            StatusCode::Ok => Ok(body),
+            status => {
+                let retry_after = response.headers.get_raw("Retry-After")
+                    .and_then(|values| values.first())
+                    .and_then(|value| parse_retry_after(&String::from_utf8_lossy(value)));
+
+                Err(Error::ResponseNotOk { status: status.to_u16(), body: excerpt(&body), retry_after: retry_after })
+            }
+        }
+    }
+
+    fn decode(&self, body: &str) -> Result<Fares, Error> {
+        decode(body)
+    }
+}
+
+pub fn encode(request: &Request) -> Result<String, Error> {
+    let passengers = Passengers {
+        kind: PASSENGER_COUNT_KIND,
+        adultCount: request.passengers.adults,
+        childCount: request.passengers.children,
+        infantInLapCount: request.passengers.infants_in_lap,
+        infantInSeatCount: request.passengers.infants_in_seat,
+        seniorCount: request.passengers.seniors
+    };
+
+    let slices = request.slices.iter().map(|slice| {
+        SliceInput {
+            kind: SLICE_KIND,
+            origin: slice.origin.clone(),
+            destination: slice.destination.clone(),
+            date: slice.date.clone(),
+            maxStops: slice.max_stops,
+            maxConnectionDuration: slice.max_connection_duration,
+            preferredCabin: slice.preferred_cabin.clone(),
+            permittedConnectionPoint: slice.permitted_connection_points.clone(),
+            prohibitedConnectionPoint: slice.prohibited_connection_points.clone()
+        }
+    }).collect();
+
+    let google_search_request = GoogleSearchRequest {
+        request: GoogleRequest {
+            passengers: passengers,
+            slice: slices,
+            maxPrice: request.max_price.clone(),
+            saleCountry: Some(request.sale_country.clone()),
+            refundable: request.refundable,
+            solutions: request.solutions
+        }
+    };
+
+    json::encode(&google_search_request).map_err(|_| Error::EncodingJson)
+}
+
+pub fn decode(body: &str) -> Result<Fares, Error> {
+    let search_response: SearchResponse = try!(json::decode(body).map_err(|_| Error::DecodingJson(body.to_string())));
+
+    search_response.to_fares()
+}
+
+fn to_taxes(passenger_type: &str, taxes: &Option<Vec<TaxInfo>>) -> Result<Vec<OfferTax>, Error> {
+    let mut offer_taxes = vec!();
+
+    for tax in taxes.iter().flat_map(|taxes| taxes.iter()) {
+        let (amount, currency) = try!(money::parse(&tax.salePrice));
+
+        offer_taxes.push(OfferTax {
+            id: None,
+            offer_id: None,
+            passenger_type: passenger_type.to_string(),
+            tax_id: tax.id.clone(),
+            name: None,
+            code: tax.code.clone(),
+            country: tax.country.clone(),
+            charge_type: tax.chargeType.clone(),
+            amount: amount,
+            currency: currency.to_string()
+        });
+    }
+
+    Ok(offer_taxes)
+}
+
+fn parse_time(time: String) -> Result<Tm, Error> {
+    strptime(&time, ISO_TIME_FORMAT).map_err(|_| Error::ParsingTime(time))
+}
@@ -0,0 +1,143 @@
+use std::fs::File;
+use std::io::Read;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread::sleep;
+use std::time::Duration;
+
+use regex::{Regex, Captures};
+
+use flights::Request;
+use flights::search::Fares;
+use providers::{qpx, excerpt, FareProvider};
+use money::round;
+use http::parse_retry_after;
+use Error;
+
+const DEFAULT_RESPONSE: &'static str = "default";
+const EXTENSIONS: [&'static str; 2] = ["json", "http"];
+
+#[derive(RustcDecodable, PartialEq)]
+pub struct ReplayConfig {
+    pub dir: String,
+    pub latency_ms: Option<u64>,
+    pub fail_every: Option<usize>,
+    pub drift: Option<f64>
+}
+
+/// Responses are looked up by their slices, e.g. `TXL-OTP-2016-03-28_OTP-TXL-2016-04-03.json`,
+/// falling back to `default.json`.
+pub struct Replay {
+    dir: PathBuf,
+    latency: Option<Duration>,
+    fail_every: Option<usize>,
+    drift: f64,
+    calls: AtomicUsize
+}
+
+impl Replay {
+    pub fn new(config: &ReplayConfig) -> Self {
+        Replay {
+            dir: PathBuf::from(&config.dir),
+            latency: config.latency_ms.map(Duration::from_millis),
+            fail_every: config.fail_every,
+            drift: config.drift.unwrap_or(0.0),
+            calls: AtomicUsize::new(0)
+        }
+    }
+
+    fn read_response(&self, name: &str) -> Result<Option<String>, Error> {
+        for extension in &EXTENSIONS {
+            let path = self.dir.join(format!("{}.{}", name, extension));
+
+            if let Ok(mut file) = File::open(&path) {
+                let mut buf = String::new();
+                try!(file.read_to_string(&mut buf).map_err(|err| Error::ReadingReplay(format!("{}: {}", path.display(), err))));
+
+                return Ok(Some(buf))
+            }
+        }
+
+        Ok(None)
+    }
+}
+
+impl FareProvider for Replay {
+    fn name(&self) -> &str {
+        "replay"
+    }
+
+    fn query(&self, request: &Request) -> Result<String, Error> {
+        let slices: Vec<String> = request.slices.iter().map(|slice| {
+            format!("{}-{}-{}", slice.origin, slice.destination, slice.date)
+        }).collect();
+
+        Ok(slices.join("_"))
+    }
+
+    fn execute(&self, query: &str) -> Result<String, Error> {
+        let call = self.calls.fetch_add(1, Ordering::SeqCst) + 1;
+
+        if let Some(latency) = self.latency { sleep(latency); }
+
+        if let Some(fail_every) = self.fail_every {
+            if fail_every > 0 && call % fail_every == 0 {
+                return Err(Error::ResponseNotOk { status: 503, body: "simulated failure".to_string(), retry_after: None })
+            }
+        }
+
+        let response = match try!(self.read_response(query)) {
+            Some(response) => response,
+            None => try!(try!(self.read_response(DEFAULT_RESPONSE))
+                .ok_or(Error::ReadingReplay(format!("no recorded response for {} in {}", query, self.dir.display()))))
+        };
+
+        let body = try!(strip_http(response));
+
+        drift_prices(&body, 1.0 + self.drift * ((call - 1) as f64))
+    }
+
+    fn decode(&self, body: &str) -> Result<Fares, Error> {
+        qpx::decode(body)
+    }
+}
+
+/// Done on the body so that decoding it again gives the prices of the call that returned it.
+fn drift_prices(body: &str, factor: f64) -> Result<String, Error> {
+    if factor == 1.0 { return Ok(body.to_string()) }
+
+    let regexp = try!(Regex::new(r#""(?P<currency>[A-Z]{3})(?P<amount>\d+\.\d+)""#).map_err(|err| Error::ReadingReplay(err.to_string())));
+
+    Ok(regexp.replace_all(body, |captures: &Captures| {
+        let amount = captures.name("amount").and_then(|amount| amount.parse::<f64>().ok()).unwrap_or(0.0);
+
+        format!("\"{}{:.2}\"", captures.name("currency").unwrap_or(""), round(amount * factor))
+    }))
+}
+
+fn strip_http(response: String) -> Result<String, Error> {
+    if !response.starts_with("HTTP/") { return Ok(response) }
+
+    let body_start = response.find("\r\n\r\n").map(|position| position + 4)
+        .or(response.find("\n\n").map(|position| position + 2))
+        .unwrap_or(response.len());
+
+    let mut head = response[..body_start].lines();
+    let body = &response[body_start..];
+
+    let status_line = head.next().unwrap_or("");
+    let status = try!(status_line.split_whitespace().nth(1).and_then(|code| code.parse::<u16>().ok())
+        .ok_or(Error::ReadingReplay(format!("invalid status line {}", status_line))));
+
+    if status == 200 { return Ok(body.to_string()) }
+
+    let retry_after = head.filter_map(|header| {
+        let mut parts = header.splitn(2, ':');
+        match (parts.next(), parts.next()) {
+            (Some(name), Some(value)) if name.trim().to_lowercase() == "retry-after" => parse_retry_after(value),
+            _ => None
+        }
+    }).next();
+
+    Err(Error::ResponseNotOk { status: status, body: excerpt(body), retry_after: retry_after })
+}
@@ -0,0 +1,148 @@
+use std::cmp::min;
+use std::sync::{Arc, Mutex};
+use std::thread::sleep;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use flights::Request;
+use flights::search::Fares;
+use providers::FareProvider;
+use providers::throttle::Throttle;
+use quota::{Allowance, Call};
+use Error;
+
+const DEFAULT_ATTEMPTS: u32 = 3;
+const DEFAULT_BASE_DELAY_MS: u64 = 1000;
+const DEFAULT_MAX_DELAY_MS: u64 = 60000;
+
+#[derive(RustcDecodable, PartialEq)]
+pub struct RetryConfig {
+    pub attempts: Option<u32>,
+    pub base_delay_ms: Option<u64>,
+    pub max_delay_ms: Option<u64>
+}
+
+pub struct RetryPolicy {
+    pub attempts: u32,
+    pub base_delay: Duration,
+    pub max_delay: Duration
+}
+
+/// Retries network errors, timeouts, 408, 429 and 5xx responses with backoff. Every attempt
+/// counts against the allowance and the daily quota.
+pub struct Retrying {
+    provider: Box<FareProvider>,
+    policy: RetryPolicy,
+    throttle: Option<Throttle>,
+    allowance: Option<Arc<Allowance>>,
+    calls: Mutex<Vec<Call>>
+}
+
+impl RetryPolicy {
+    pub fn new(config: Option<&RetryConfig>) -> Self {
+        let attempts = config.and_then(|config| config.attempts).unwrap_or(DEFAULT_ATTEMPTS);
+        let base_delay_ms = config.and_then(|config| config.base_delay_ms).unwrap_or(DEFAULT_BASE_DELAY_MS);
+        let max_delay_ms = config.and_then(|config| config.max_delay_ms).unwrap_or(DEFAULT_MAX_DELAY_MS);
+
+        RetryPolicy {
+            attempts: attempts,
+            base_delay: Duration::from_millis(base_delay_ms),
+            max_delay: Duration::from_millis(max_delay_ms)
+        }
+    }
+
+    /// `retry` starts at 1, `None` if the error shouldn't be retried at all.
+    pub fn delay(&self, error: &Error, retry: u32) -> Option<Duration> {
+        if retry >= self.attempts || !is_transient(error) { return None }
+
+        if let Error::ResponseNotOk { retry_after: Some(seconds), .. } = *error {
+            let retry_after = Duration::from_secs(seconds);
+
+            // retrying any earlier is a paid call that most likely fails again
+            return if retry_after <= self.max_delay { Some(retry_after) } else { None }
+        }
+
+        let backoff = self.base_delay * 2u32.pow(min(retry - 1, 16));
+        let capped = min(backoff, self.max_delay);
+
+        Some(capped / 2 + jitter(capped / 2))
+    }
+}
+
+impl Retrying {
+    pub fn new(provider: Box<FareProvider>, policy: RetryPolicy) -> Self {
+        Retrying { provider: provider, policy: policy, throttle: None, allowance: None, calls: Mutex::new(vec!()) }
+    }
+
+    pub fn throttled(mut self, throttle: Throttle) -> Self {
+        self.throttle = Some(throttle);
+
+        self
+    }
+
+    pub fn limited(mut self, allowance: Arc<Allowance>) -> Self {
+        self.allowance = Some(allowance);
+
+        self
+    }
+
+    pub fn take_calls(&self) -> Vec<Call> {
+        let mut calls = self.calls.lock().unwrap();
+
+        calls.drain(..).collect()
+    }
+}
+
+impl FareProvider for Retrying {
+    fn name(&self) -> &str {
+        self.provider.name()
+    }
+
+    fn query(&self, request: &Request) -> Result<String, Error> {
+        self.provider.query(request)
+    }
+
+    fn execute(&self, query: &str) -> Result<String, Error> {
+        let mut retry = 0;
+
+        loop {
+            if let Some(ref allowance) = self.allowance { try!(allowance.take()); }
+            if let Some(ref throttle) = self.throttle { throttle.wait(); }
+
+            let result = self.provider.execute(query);
+            self.calls.lock().unwrap().push(Call::new(self.name(), &result));
+
+            let error = match result {
+                Ok(body) => return Ok(body),
+                Err(error) => error
+            };
+
+            retry += 1;
+
+            match self.policy.delay(&error, retry) {
+                Some(delay) => sleep(delay),
+                None => return Err(error)
+            }
+        }
+    }
+
+    fn decode(&self, body: &str) -> Result<Fares, Error> {
+        self.provider.decode(body)
+    }
+}
+
+pub fn is_transient(error: &Error) -> bool {
+    match *error {
+        Error::SendingRequest(_) | Error::ReadingResponse(_) | Error::Timeout(_) => true,
+        Error::ResponseNotOk { status, .. } => status == 408 || status == 429 || status >= 500,
+        _ => false
+    }
+}
+
+fn jitter(max: Duration) -> Duration {
+    let max_ms = max.as_secs() * 1000 + (max.subsec_nanos() / 1000000) as u64;
+    if max_ms == 0 { return Duration::from_millis(0) }
+
+    let nanos = SystemTime::now().duration_since(UNIX_EPOCH).map(|now| now.subsec_nanos()).unwrap_or(0);
+
+    Duration::from_millis(nanos as u64 % max_ms)
+}
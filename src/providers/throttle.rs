@@ -0,0 +1,36 @@
+use std::sync::Mutex;
+use std::thread::sleep;
+use std::time::{Duration, Instant};
+
+pub struct Throttle {
+    interval: Duration,
+    next: Mutex<Option<Instant>>
+}
+
+impl Throttle {
+    pub fn new(calls_per_second: f64) -> Self {
+        let interval_ns = (1000000000.0 / calls_per_second) as u64;
+
+        Throttle {
+            interval: Duration::new(interval_ns / 1000000000, (interval_ns % 1000000000) as u32),
+            next: Mutex::new(None)
+        }
+    }
+
+    pub fn wait(&self) {
+        let delay = {
+            let mut next = self.next.lock().unwrap();
+            let now = Instant::now();
+
+            let slot = match *next {
+                Some(next) if next > now => next,
+                _ => now
+            };
+
+            *next = Some(slot + self.interval);
+            slot - now
+        };
+
+        if delay > Duration::from_secs(0) { sleep(delay) }
+    }
+}
@@ -0,0 +1,110 @@
+use std::sync::Mutex;
+
+use time::{now, Duration, Timespec, Tm};
+use rusqlite::Connection;
+
+use session::midnight;
+use Error;
+
+pub struct Call {
+    pub id: Option<i64>,
+    pub provider: String,
+    pub made_at: Timespec,
+    pub status: Option<u16>,
+    pub error: Option<String>
+}
+
+impl Call {
+    pub fn new(provider: &str, result: &Result<String, Error>) -> Self {
+        let (status, error) = match *result {
+            Ok(_) => (Some(200), None),
+            Err(Error::ResponseNotOk { status, .. }) => (Some(status), None),
+            Err(ref error) => (None, Some(format!("{:?}", error)))
+        };
+
+        Call {
+            id: None,
+            provider: provider.to_string(),
+            made_at: now().to_timespec(),
+            status: status,
+            error: error
+        }
+    }
+
+    pub fn create(&mut self, conn: &Connection) -> Result<(), Error> {
+        let mut sql = try!(conn.prepare(
+            "INSERT INTO calls
+                (
+                    provider,
+                    made_at,
+                    status,
+                    error
+                ) VALUES (?, ?, ?, ?)"
+            ).map_err(|err| Error::PreparingDbQuery(err.to_string())));
+
+        try!(sql.execute(
+            &[
+                &self.provider,
+                &self.made_at,
+                &self.status.map(|status| status as i64),
+                &self.error
+            ]).map_err(|err| Error::ExecutingDbQuery(err.to_string())));
+
+        self.id = Some(conn.last_insert_rowid());
+
+        Ok(())
+    }
+}
+
+/// The calls that may still be made, shared by the providers so that retries count too.
+pub struct Allowance {
+    remaining: Mutex<Option<usize>>
+}
+
+impl Allowance {
+    pub fn new() -> Self {
+        Allowance { remaining: Mutex::new(None) }
+    }
+
+    pub fn set(&self, calls: usize) {
+        *self.remaining.lock().unwrap() = Some(calls);
+    }
+
+    pub fn take(&self) -> Result<(), Error> {
+        let mut remaining = self.remaining.lock().unwrap();
+
+        match *remaining {
+            Some(0) => Err(Error::QuotaUsedUp),
+            Some(calls) => {
+                *remaining = Some(calls - 1);
+                Ok(())
+            },
+            None => Ok(())
+        }
+    }
+}
+
+pub fn calls_today(conn: &Connection) -> Result<usize, Error> {
+    let mut sql = try!(conn.prepare("SELECT COUNT(*) FROM calls WHERE made_at >= ?")
+        .map_err(|err| Error::PreparingDbQuery(err.to_string())));
+
+    let mut rows = try!(sql.query(&[&midnight().to_timespec()]).map_err(|err| Error::ExecutingDbQuery(err.to_string())));
+
+    match rows.next() {
+        Some(row) => {
+            let data = try!(row.map_err(|err| Error::UnwrappingDbRow(err.to_string())));
+            Ok(data.get::<i64>(0) as usize)
+        },
+        None => Ok(0)
+    }
+}
+
+pub fn remaining(conn: &Connection, calls_per_day: usize) -> Result<usize, Error> {
+    let calls = try!(calls_today(conn));
+
+    Ok(calls_per_day.saturating_sub(calls))
+}
+
+pub fn next_reset() -> Tm {
+    midnight() + Duration::days(1)
+}
@@ -0,0 +1,49 @@
+use std::cmp::min;
+use std::fs::metadata;
+use std::thread::sleep;
+use std::time::{Duration, SystemTime};
+
+use paths::Location;
+
+const POLL_INTERVAL_SECONDS: u64 = 5;
+
+pub struct ConfigWatcher {
+    location: Location,
+    modified: Option<SystemTime>
+}
+
+impl ConfigWatcher {
+    pub fn new(location: &Location) -> Self {
+        ConfigWatcher {
+            location: location.clone(),
+            modified: modified_at(location)
+        }
+    }
+
+    pub fn changed(&mut self) -> bool {
+        let modified = modified_at(&self.location);
+        if modified == self.modified { return false }
+
+        self.modified = modified;
+        true
+    }
+
+    pub fn wait(&mut self, duration: Duration) -> bool {
+        let poll_interval = Duration::from_secs(POLL_INTERVAL_SECONDS);
+        let mut remaining = duration;
+
+        while remaining > Duration::from_secs(0) {
+            let step = min(remaining, poll_interval);
+            sleep(step);
+            remaining = remaining - step;
+
+            if self.changed() { return true }
+        }
+
+        false
+    }
+}
+
+fn modified_at(location: &Location) -> Option<SystemTime> {
+    metadata(&location.path).and_then(|metadata| metadata.modified()).ok()
+}
@@ -0,0 +1,122 @@
+use std::env;
+use std::fmt::{Debug, Display, Formatter};
+use std::fmt::Result as FmtResult;
+use std::fs::File;
+use std::io::Read;
+use std::process::Command;
+
+use rustc_serialize::{Decodable, Decoder};
+
+use Error;
+
+const REDACTED: &'static str = "[REDACTED]";
+const ONE_SOURCE: &'static str = "exactly one of env, file or command has to be given";
+
+/// A credential which never shows up in `Debug` or `Display` output.
+#[derive(Clone, PartialEq)]
+pub struct Secret(String);
+
+#[derive(RustcDecodable, Debug, PartialEq)]
+pub struct SecretSource {
+    pub env: Option<String>,
+    pub file: Option<String>,
+    pub command: Option<String>
+}
+
+impl Secret {
+    pub fn new(value: &str) -> Self {
+        Secret(value.trim().to_string())
+    }
+
+    pub fn expose(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Decodable for Secret {
+    fn decode<D: Decoder>(decoder: &mut D) -> Result<Self, D::Error> {
+        decoder.read_str().map(|value| Secret::new(&value))
+    }
+}
+
+impl Debug for Secret {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        write!(f, "Secret({})", REDACTED)
+    }
+}
+
+impl Display for Secret {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        write!(f, "{}", REDACTED)
+    }
+}
+
+impl SecretSource {
+    /// Checks the source without reading the secret, which may run a command.
+    pub fn validate(&self) -> Result<(), Error> {
+        match (&self.env, &self.file, &self.command) {
+            (&Some(_), &None, &None) | (&None, &Some(_), &None) | (&None, &None, &Some(_)) => Ok(()),
+            _ => Err(Error::ResolvingSecret(ONE_SOURCE.to_string()))
+        }
+    }
+
+    pub fn resolve(&self) -> Result<Secret, Error> {
+        let secret = match (&self.env, &self.file, &self.command) {
+            (&Some(ref name), &None, &None) => try!(from_env(name)),
+            (&None, &Some(ref path), &None) => try!(from_file(path)),
+            (&None, &None, &Some(ref command)) => try!(from_command(command)),
+            _ => return Err(Error::ResolvingSecret(ONE_SOURCE.to_string()))
+        };
+
+        if secret.expose().is_empty() {
+            return Err(Error::ResolvingSecret("the secret is empty".to_string()))
+        }
+
+        Ok(secret)
+    }
+}
+
+fn from_env(name: &str) -> Result<Secret, Error> {
+    env::var(name).map(|value| Secret::new(&value)).map_err(|_| Error::ResolvingSecret(format!("${} is not set", name)))
+}
+
+fn from_file(path: &str) -> Result<Secret, Error> {
+    let mut file = try!(File::open(path).map_err(|err| Error::ResolvingSecret(format!("{}: {}", path, err))));
+    try!(check_permissions(&file, path));
+
+    let mut buf = String::new();
+    try!(file.read_to_string(&mut buf).map_err(|err| Error::ResolvingSecret(format!("{}: {}", path, err))));
+
+    Ok(Secret::new(&buf))
+}
+
+#[cfg(unix)]
+fn check_permissions(file: &File, path: &str) -> Result<(), Error> {
+    use std::os::unix::fs::PermissionsExt;
+
+    let metadata = try!(file.metadata().map_err(|err| Error::ResolvingSecret(format!("{}: {}", path, err))));
+
+    if metadata.permissions().mode() & 0o077 != 0 {
+        return Err(Error::ResolvingSecret(format!("{} can be accessed by other users, restrict it with chmod 600", path)))
+    }
+
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn check_permissions(_: &File, _: &str) -> Result<(), Error> {
+    Ok(())
+}
+
+fn from_command(command: &str) -> Result<Secret, Error> {
+    let output = try!(Command::new("sh").arg("-c").arg(command).output()
+        .map_err(|err| Error::ResolvingSecret(format!("{}: {}", command, err))));
+
+    if !output.status.success() {
+        return Err(Error::ResolvingSecret(format!("{} exited with {}", command, output.status)))
+    }
+
+    let stdout = try!(String::from_utf8(output.stdout).map_err(|_| Error::ResolvingSecret(format!("{} printed invalid UTF-8", command))));
+
+    Ok(Secret::new(&stdout))
+}
@@ -1,72 +1,391 @@
-use std::fs::{File, remove_file};
+use std::fs::{File, create_dir_all, remove_file};
 use std::io::Read;
+use std::path::Path;
 use std::convert::From;
 
 use std::time::Duration as StdDuration;
 use time::{now, Duration, Tm, Timespec};
-use toml::decode_str;
+use toml::{Parser, Decoder, DecodeError, Value, Table};
+use rustc_serialize::Decodable;
 use rusqlite::Connection;
 
 use flights::Request;
+use diagnostics::{Diagnostic, child_key, locate};
+use paths::Location;
+use secrets::{Secret, SecretSource};
+use providers::replay::ReplayConfig;
+use providers::retry::RetryConfig;
+use http::{HttpConfig, parse_proxy};
+use executor::{ConcurrencyConfig, DEFAULT_CONCURRENCY};
+use cache::{Cache, DEFAULT_TTL_MINUTES};
+use providers::PROVIDERS;
+use watch::{Watch, Trip};
 use Error;
 
-const DEFAULT_CONFIG_PATH: &'static str = "config.toml";
-const DEFAULT_DB_PATH: &'static str = "data.sqlite";
+const ONE_KEY_SOURCE: &'static str = "exactly one of google_api_key or google_api_key_from has to be given";
+
+/// Columns missing from databases created by older versions.
+const ADDED_COLUMNS: [(&'static str, &'static str); 16] = [
+    ("requests", "provider TEXT"),
+    ("requests", "adult_count INTEGER NOT NULL DEFAULT 1"),
+    ("requests", "child_count INTEGER NOT NULL DEFAULT 0"),
+    ("requests", "senior_count INTEGER NOT NULL DEFAULT 0"),
+    ("requests", "infant_in_lap_count INTEGER NOT NULL DEFAULT 0"),
+    ("requests", "infant_in_seat_count INTEGER NOT NULL DEFAULT 0"),
+    ("requests", "max_price TEXT"),
+    ("requests", "refundable INTEGER"),
+    ("requests", "solutions INTEGER"),
+    ("requests", "cache_hit INTEGER NOT NULL DEFAULT 0"),
+    ("requests", "provider_request_id TEXT"),
+    ("request_slices", "permitted_connection_points TEXT"),
+    ("request_slices", "prohibited_connection_points TEXT"),
+    ("offers", "stops INTEGER NOT NULL DEFAULT 0"),
+    ("offers", "provider_id TEXT"),
+    ("offer_taxes", "passenger_type TEXT NOT NULL DEFAULT 'ADT'")
+];
 
 #[derive(RustcDecodable)]
 pub struct Session {
     pub email: Option<String>,
-    pub google_api_key: String,
+    pub google_api_key: Option<Secret>,
+    pub google_api_key_from: Option<SecretSource>,
     pub requests_per_day: usize,
     pub sale_country: String,
-    pub request_name: String,
-    pub trips: Vec<Trip>
-}
-
-#[derive(RustcDecodable)]
-pub struct Trip {
-    pub from: String,
-    pub to: String,
-    pub dates: Vec<String>
+    pub replay: Option<ReplayConfig>,
+    pub archive: Option<bool>,
+    pub cache_ttl_minutes: Option<i64>,
+    pub retry: Option<RetryConfig>,
+    pub http: Option<HttpConfig>,
+    pub concurrency: Option<ConcurrencyConfig>,
+    pub watches: Vec<Watch>
 }
 
 impl Session {
-    pub fn load() -> Result<Self, Error> {
-        let mut file = try!(File::open(DEFAULT_CONFIG_PATH).map_err(|_| Error::LoadingConfig));
+    pub fn load(location: &Location) -> Result<Self, Error> {
+        let source = try!(Session::read_config(location));
+
+        Session::from_toml(&source)
+    }
+
+    pub fn read_config(location: &Location) -> Result<String, Error> {
+        let mut file = try!(File::open(&location.path).map_err(|err| Error::LoadingConfig(format!("{}: {}", location, err))));
         let mut buf = String::new();
 
-        try!(file.read_to_string(&mut buf).map_err(|_| Error::ReadingConfig));
+        try!(file.read_to_string(&mut buf).map_err(|err| Error::ReadingConfig(format!("{}: {}", location, err))));
+
+        Ok(buf)
+    }
+
+    pub fn from_toml(source: &str) -> Result<Self, Error> {
+        let (session, diagnostics) = Session::check(source);
+        let errors: Vec<Diagnostic> = diagnostics.into_iter().filter(|diagnostic| diagnostic.is_error()).collect();
+
+        match session {
+            Some(session) if errors.is_empty() => Ok(session),
+            _ => Err(Error::InvalidConfig(errors))
+        }
+    }
+
+    pub fn check(source: &str) -> (Option<Self>, Vec<Diagnostic>) {
+        let mut parser = Parser::new(source);
+
+        let table = match parser.parse() {
+            Some(table) => table,
+            None => {
+                let diagnostics = parser.errors.iter().map(|error| {
+                    let mut diagnostic = Diagnostic::error("", &error.desc);
+                    diagnostic.line = Some(parser.to_linecol(error.lo).0 + 1);
+                    diagnostic
+                }).collect();
+
+                return (None, diagnostics)
+            }
+        };
+
+        let mut decoder = Decoder::new(Value::Table(table.clone()));
+
+        match Session::decode(&mut decoder) {
+            Ok(session) => {
+                let mut diagnostics = unknown_keys(&table, decoder.toml);
+                diagnostics.extend(session.validate());
+                (Some(session), locate_all(source, diagnostics))
+            },
+            Err(error) => (None, locate_all(source, vec!(decode_diagnostic(&table, error))))
+        }
+    }
+
+    pub fn diff(&self, other: &Session) -> Vec<String> {
+        let mut changes = vec!();
+
+        for watch in &other.watches {
+            match self.watches.iter().find(|current| current.name == watch.name) {
+                None => changes.push(format!("+ watch {}", watch.name)),
+                Some(current) if current != watch => changes.push(format!("~ watch {}", watch.name)),
+                _ => {}
+            }
+        }
+
+        for watch in &self.watches {
+            if !other.watches.iter().any(|updated| updated.name == watch.name) {
+                changes.push(format!("- watch {}", watch.name));
+            }
+        }
+
+        if self.requests_per_day != other.requests_per_day {
+            changes.push(format!("~ requests_per_day {} -> {}", self.requests_per_day, other.requests_per_day));
+        }
+
+        if self.sale_country != other.sale_country {
+            changes.push(format!("~ sale_country {} -> {}", self.sale_country, other.sale_country));
+        }
+
+        if self.replay != other.replay {
+            changes.push("~ replay".to_string());
+        }
+
+        if self.concurrency != other.concurrency {
+            changes.push("~ concurrency".to_string());
+        }
+
+        if self.http != other.http {
+            changes.push("~ http".to_string());
+        }
+
+        if self.retry != other.retry {
+            changes.push("~ retry".to_string());
+        }
+
+        if self.cache_ttl_minutes != other.cache_ttl_minutes {
+            changes.push(format!("~ cache_ttl_minutes {} -> {}", self.cache_ttl_minutes(), other.cache_ttl_minutes()));
+        }
+
+        if self.archive != other.archive {
+            changes.push(format!("~ archive {} -> {}", self.archive(), other.archive()));
+        }
+
+        if self.google_api_key != other.google_api_key || self.google_api_key_from != other.google_api_key_from {
+            changes.push("~ google_api_key".to_string());
+        }
+
+        changes
+    }
+
+    pub fn concurrency(&self) -> usize {
+        self.concurrency.as_ref().and_then(|concurrency| concurrency.limit).unwrap_or(DEFAULT_CONCURRENCY)
+    }
+
+    pub fn calls_per_second(&self, provider: &str) -> Option<f64> {
+        self.concurrency.as_ref()
+            .and_then(|concurrency| concurrency.calls_per_second.as_ref())
+            .and_then(|calls_per_second| calls_per_second.get(provider).cloned())
+    }
+
+    pub fn cache_ttl_minutes(&self) -> i64 {
+        self.cache_ttl_minutes.unwrap_or(DEFAULT_TTL_MINUTES)
+    }
+
+    pub fn cache(&self) -> Cache {
+        Cache::new(self.cache_ttl_minutes())
+    }
+
+    pub fn archive(&self) -> bool {
+        self.archive.unwrap_or(false)
+    }
+
+    pub fn google_api_key(&self) -> Result<Secret, Error> {
+        match (&self.google_api_key, &self.google_api_key_from) {
+            (&Some(ref key), &None) => Ok(key.clone()),
+            (&None, &Some(ref source)) => source.resolve(),
+            _ => Err(Error::ResolvingSecret(ONE_KEY_SOURCE.to_string()))
+        }
+    }
+
+    fn validate(&self) -> Vec<Diagnostic> {
+        let mut diagnostics = vec!();
 
-        decode_str(&buf).ok_or(Error::ParsingConfig)
+        if self.watches.iter().any(|watch| watch.provider() == "qpx") {
+            let sources = match (&self.google_api_key, &self.google_api_key_from) {
+                (&Some(_), &None) => Ok(()),
+                (&None, &Some(ref source)) => source.validate(),
+                _ => Err(Error::ResolvingSecret(ONE_KEY_SOURCE.to_string()))
+            };
+
+            // the key itself is only read by the providers, as that may run a command
+            if let Err(Error::ResolvingSecret(message)) = sources {
+                let key = if self.google_api_key_from.is_some() { "google_api_key_from" } else { "google_api_key" };
+                diagnostics.push(Diagnostic::error(key, &message));
+            }
+        }
+
+        if self.watches.iter().any(|watch| watch.provider() == "replay") {
+            match self.replay {
+                Some(ref replay) if !Path::new(&replay.dir).is_dir() => {
+                    diagnostics.push(Diagnostic::error("replay.dir", &format!("{} is not a directory", replay.dir)));
+                },
+                None => diagnostics.push(Diagnostic::error("replay", "the replay provider needs a [replay] table")),
+                _ => {}
+            }
+        }
+
+        if self.requests_per_day == 0 {
+            diagnostics.push(Diagnostic::error("requests_per_day", "has to be greater than 0"));
+        }
+
+        if let Some(ref http) = self.http {
+            if let Some(Err(Error::InvalidProxy(message))) = http.proxy.as_ref().map(|proxy| parse_proxy(proxy)) {
+                diagnostics.push(Diagnostic::error("http.proxy", &message));
+            }
+
+            if http.connect_timeout_ms == Some(0) || http.read_timeout_ms == Some(0) {
+                diagnostics.push(Diagnostic::error("http", "timeouts have to be greater than 0"));
+            }
+        }
+
+        if self.cache_ttl_minutes() < 0 {
+            diagnostics.push(Diagnostic::error("cache_ttl_minutes", "can't be negative"));
+        }
+
+        if let Some(ref concurrency) = self.concurrency {
+            if concurrency.limit == Some(0) {
+                diagnostics.push(Diagnostic::error("concurrency.limit", "has to be greater than 0"));
+            }
+
+            if let Some(ref calls_per_second) = concurrency.calls_per_second {
+                let mut providers: Vec<&String> = calls_per_second.keys().collect();
+                providers.sort();
+
+                for provider in providers {
+                    let key = format!("concurrency.calls_per_second.{}", provider);
+
+                    if !PROVIDERS.contains(&provider.as_str()) {
+                        diagnostics.push(Diagnostic::error(&key, &format!("{} is not one of {}", provider, PROVIDERS.join(", "))));
+                    } else if !(calls_per_second[provider] > 0.0) {
+                        diagnostics.push(Diagnostic::error(&key, "has to be greater than 0"));
+                    }
+                }
+            }
+        }
+
+        if let Some(ref retry) = self.retry {
+            if retry.attempts == Some(0) {
+                diagnostics.push(Diagnostic::error("retry.attempts", "has to be greater than 0"));
+            }
+
+            if let (Some(base), Some(max)) = (retry.base_delay_ms, retry.max_delay_ms) {
+                if base > max {
+                    diagnostics.push(Diagnostic::error("retry.base_delay_ms", "can't be greater than max_delay_ms"));
+                }
+            }
+        }
+
+        if self.sale_country.len() != 2 || !self.sale_country.chars().all(|c| c.is_ascii_uppercase()) {
+            diagnostics.push(Diagnostic::error("sale_country", &format!("{} is not a two-letter country code", self.sale_country)));
+        }
+
+        if self.watches.is_empty() {
+            diagnostics.push(Diagnostic::error("watches", "at least one watch is required"));
+        }
+
+        let mut all_valid = true;
+
+        for (index, watch) in self.watches.iter().enumerate() {
+            let key = format!("watches[{}]", index);
+
+            if self.watches[..index].iter().any(|other| other.name == watch.name) {
+                diagnostics.push(Diagnostic::error(&child_key(&key, "name"), &format!("{} is used by another watch", watch.name)));
+            }
+
+            let watch_diagnostics = watch.check(&key);
+            let valid = !watch_diagnostics.iter().any(|diagnostic| diagnostic.is_error());
+            all_valid = all_valid && valid;
+            diagnostics.extend(watch_diagnostics);
+
+            if valid && watch.total_calls() > 0 && self.request_sets_per_day(watch) == 0 {
+                let message = format!("its share of requests_per_day ({}) can't cover its {} searches, so it will never run", self.budget(watch), watch.total_calls());
+                diagnostics.push(Diagnostic::warning(&key, &message));
+            }
+
+            if let Ok(interval) = self.duration_per_request(watch) {
+                if self.cache().is_enabled() && Duration::minutes(self.cache_ttl_minutes()) >= interval {
+                    let message = format!("is at least the {} minutes between runs of {}, so every other run would be answered from the cache", interval.num_minutes(), watch.name);
+                    diagnostics.push(Diagnostic::warning("cache_ttl_minutes", &message));
+                }
+            }
+        }
+
+        if all_valid && !self.watches.is_empty() && self.idle_watches().len() == self.watches.len() {
+            diagnostics.push(Diagnostic::error("watches", "none of the watches can ever run"));
+        }
+
+        diagnostics
     }
 
     pub fn total_calls(&self) -> usize {
-        if self.trips.len() == 0 { return 0 }
+        self.watches.iter().fold(0, |acc, watch| acc + watch.total_calls())
+    }
+
+    pub fn budget(&self, watch: &Watch) -> usize {
+        let total_shares = self.watches.iter().fold(0, |acc, watch| acc + watch.share());
+        if total_shares == 0 { return 0 }
 
-        self.trips.iter().fold(1, |acc, trip| acc * trip.dates.len())
+        self.requests_per_day * watch.share() / total_shares
     }
 
-    pub fn request_sets_per_day(&self) -> usize {
-        if self.total_calls() == 0 { return 0 }
+    pub fn request_sets_per_day(&self, watch: &Watch) -> usize {
+        if watch.total_calls() == 0 { return 0 }
 
-        self.requests_per_day / self.total_calls()
+        self.budget(watch) / watch.total_calls()
     }
 
-    pub fn db_connection() -> Result<Connection, Error> {
-        Connection::open(DEFAULT_DB_PATH).map_err(|_| Error::EstablishingDbConnection)
+    pub fn db_connection(location: &Location) -> Result<Connection, Error> {
+        if let Some(dir) = location.path.parent() {
+            if !dir.as_os_str().is_empty() {
+                try!(create_dir_all(dir).map_err(|err| Error::EstablishingDbConnection(format!("{}: {}", location, err))));
+            }
+        }
+
+        Connection::open(&location.path).map_err(|err| Error::EstablishingDbConnection(format!("{}: {}", location, err)))
     }
 
-    pub fn db_setup(&self, conn: &Connection) -> Result<(), Error> {
+    pub fn db_setup(conn: &Connection) -> Result<(), Error> {
         let create_requests = conn.execute(
             "CREATE TABLE IF NOT EXISTS requests
             (
                 id INTEGER PRIMARY KEY,
                 name TEXT NOT NULL,
-                created_at INTEGER NOT NULL
+                created_at INTEGER NOT NULL,
+                provider TEXT,
+                adult_count INTEGER NOT NULL,
+                child_count INTEGER NOT NULL,
+                senior_count INTEGER NOT NULL,
+                infant_in_lap_count INTEGER NOT NULL,
+                infant_in_seat_count INTEGER NOT NULL,
+                max_price TEXT,
+                refundable INTEGER,
+                solutions INTEGER,
+                cache_hit INTEGER NOT NULL DEFAULT 0,
+                provider_request_id TEXT
             )", &[]);
 
         try!(create_requests.map_err(|err| Error::ExecutingDbQuery(err.to_string())));
 
+        let create_request_slices = conn.execute(
+            "CREATE TABLE IF NOT EXISTS request_slices
+            (
+                id INTEGER PRIMARY KEY,
+                request_id INTEGER NOT NULL,
+                origin TEXT NOT NULL,
+                destination TEXT NOT NULL,
+                date TEXT NOT NULL,
+                max_stops INTEGER NOT NULL,
+                max_connection_duration INTEGER,
+                preferred_cabin TEXT,
+                permitted_connection_points TEXT,
+                prohibited_connection_points TEXT
+            )", &[]);
+
+        try!(create_request_slices.map_err(|err| Error::ExecutingDbQuery(err.to_string())));
+
         let create_offers = conn.execute(
             "CREATE TABLE IF NOT EXISTS offers
             (
@@ -78,62 +397,272 @@ impl Session {
                 tax_price REAL NOT NULL,
                 total_price REAL NOT NULL,
                 latest_ticketing_at INTEGER NOT NULL,
-                refundable INTEGER NOT NULL
+                refundable INTEGER NOT NULL,
+                stops INTEGER NOT NULL,
+                provider_id TEXT
             )", &[]);
 
         try!(create_offers.map_err(|err| Error::ExecutingDbQuery(err.to_string())));
 
-        let create_flights = conn.execute(
-            "CREATE TABLE IF NOT EXISTS flights
+        let create_cache = conn.execute(
+            "CREATE TABLE IF NOT EXISTS cache
+            (
+                key TEXT PRIMARY KEY,
+                query TEXT NOT NULL,
+                body BLOB NOT NULL,
+                created_at INTEGER NOT NULL
+            )", &[]);
+
+        try!(create_cache.map_err(|err| Error::ExecutingDbQuery(err.to_string())));
+
+        let create_calls = conn.execute(
+            "CREATE TABLE IF NOT EXISTS calls
+            (
+                id INTEGER PRIMARY KEY,
+                provider TEXT NOT NULL,
+                made_at INTEGER NOT NULL,
+                status INTEGER,
+                error TEXT
+            )", &[]);
+
+        try!(create_calls.map_err(|err| Error::ExecutingDbQuery(err.to_string())));
+
+        let create_archives = conn.execute(
+            "CREATE TABLE IF NOT EXISTS archives
             (
                 id INTEGER PRIMARY KEY,
-                offer_id INTEGER NOT NULL,
+                request_id INTEGER NOT NULL,
+                provider TEXT NOT NULL,
+                query TEXT NOT NULL,
+                body BLOB NOT NULL,
+                created_at INTEGER NOT NULL
+            )", &[]);
+
+        try!(create_archives.map_err(|err| Error::ExecutingDbQuery(err.to_string())));
+
+        let create_cities = conn.execute(
+            "CREATE TABLE IF NOT EXISTS cities
+            (
+                code TEXT PRIMARY KEY,
+                name TEXT NOT NULL
+            )", &[]);
+
+        try!(create_cities.map_err(|err| Error::ExecutingDbQuery(err.to_string())));
+
+        let create_airports = conn.execute(
+            "CREATE TABLE IF NOT EXISTS airports
+            (
+                code TEXT PRIMARY KEY,
+                city_code TEXT NOT NULL REFERENCES cities (code),
+                name TEXT NOT NULL
+            )", &[]);
+
+        try!(create_airports.map_err(|err| Error::ExecutingDbQuery(err.to_string())));
+
+        let create_carriers = conn.execute(
+            "CREATE TABLE IF NOT EXISTS carriers
+            (
+                code TEXT PRIMARY KEY,
+                name TEXT NOT NULL
+            )", &[]);
+
+        try!(create_carriers.map_err(|err| Error::ExecutingDbQuery(err.to_string())));
+
+        let create_aircraft = conn.execute(
+            "CREATE TABLE IF NOT EXISTS aircraft
+            (
+                code TEXT PRIMARY KEY,
+                name TEXT NOT NULL
+            )", &[]);
+
+        try!(create_aircraft.map_err(|err| Error::ExecutingDbQuery(err.to_string())));
+
+        let create_taxes = conn.execute(
+            "CREATE TABLE IF NOT EXISTS taxes
+            (
+                id TEXT PRIMARY KEY,
+                name TEXT NOT NULL
+            )", &[]);
+
+        try!(create_taxes.map_err(|err| Error::ExecutingDbQuery(err.to_string())));
+
+        let create_offer_taxes = conn.execute(
+            "CREATE TABLE IF NOT EXISTS offer_taxes
+            (
+                id INTEGER PRIMARY KEY,
+                offer_id INTEGER NOT NULL REFERENCES offers (id),
+                passenger_type TEXT NOT NULL,
+                tax_id TEXT NOT NULL REFERENCES taxes (id),
+                name TEXT,
+                code TEXT NOT NULL,
+                country TEXT,
+                charge_type TEXT,
+                amount REAL NOT NULL,
+                currency TEXT NOT NULL
+            )", &[]);
+
+        try!(create_offer_taxes.map_err(|err| Error::ExecutingDbQuery(err.to_string())));
+
+        let create_pricings = conn.execute(
+            "CREATE TABLE IF NOT EXISTS pricings
+            (
+                id INTEGER PRIMARY KEY,
+                offer_id INTEGER NOT NULL REFERENCES offers (id),
+                passenger_type TEXT NOT NULL,
+                passengers INTEGER NOT NULL,
+                currency TEXT NOT NULL,
+                base_price REAL NOT NULL,
+                sale_price REAL NOT NULL,
+                tax_price REAL NOT NULL,
+                total_price REAL NOT NULL,
+                fare_calculation TEXT NOT NULL
+            )", &[]);
+
+        try!(create_pricings.map_err(|err| Error::ExecutingDbQuery(err.to_string())));
+
+        let create_fares = conn.execute(
+            "CREATE TABLE IF NOT EXISTS fares
+            (
+                id INTEGER PRIMARY KEY,
+                pricing_id INTEGER NOT NULL REFERENCES pricings (id),
+                carrier TEXT NOT NULL REFERENCES carriers (code),
                 origin TEXT NOT NULL,
                 destination TEXT NOT NULL,
+                basis_code TEXT NOT NULL
+            )", &[]);
+
+        try!(create_fares.map_err(|err| Error::ExecutingDbQuery(err.to_string())));
+
+        let create_offer_slices = conn.execute(
+            "CREATE TABLE IF NOT EXISTS offer_slices
+            (
+                id INTEGER PRIMARY KEY,
+                offer_id INTEGER NOT NULL REFERENCES offers (id),
+                position INTEGER NOT NULL,
+                duration INTEGER NOT NULL
+            )", &[]);
+
+        try!(create_offer_slices.map_err(|err| Error::ExecutingDbQuery(err.to_string())));
+
+        let create_segments = conn.execute(
+            "CREATE TABLE IF NOT EXISTS segments
+            (
+                id INTEGER PRIMARY KEY,
+                slice_id INTEGER NOT NULL REFERENCES offer_slices (id),
+                position INTEGER NOT NULL,
+                carrier TEXT NOT NULL REFERENCES carriers (code),
+                number TEXT NOT NULL,
+                cabin TEXT NOT NULL,
+                booking_code TEXT NOT NULL,
+                booking_code_count INTEGER NOT NULL,
+                duration INTEGER NOT NULL,
+                connection_duration INTEGER
+            )", &[]);
+
+        try!(create_segments.map_err(|err| Error::ExecutingDbQuery(err.to_string())));
+
+        let create_legs = conn.execute(
+            "CREATE TABLE IF NOT EXISTS legs
+            (
+                id INTEGER PRIMARY KEY,
+                segment_id INTEGER NOT NULL REFERENCES segments (id),
+                position INTEGER NOT NULL,
+                origin TEXT NOT NULL REFERENCES airports (code),
+                destination TEXT NOT NULL REFERENCES airports (code),
                 departs_at INTEGER NOT NULL,
                 departs_at_offset INTEGER NOT NULL,
                 arrives_at INTEGER NOT NULL,
                 arrives_at_offset INTEGER NOT NULL,
                 duration INTEGER NOT NULL,
                 mileage INTEGER NOT NULL,
-                seat TEXT NOT NULL,
-                aircraft TEXT NOT NULL,
-                carrier TEXT NOT NULL,
-                number TEXT NOT NULL
+                aircraft TEXT NOT NULL REFERENCES aircraft (code),
+                meal TEXT
             )", &[]);
 
-        try!(create_flights.map_err(|err| Error::ExecutingDbQuery(err.to_string())));
+        try!(create_legs.map_err(|err| Error::ExecutingDbQuery(err.to_string())));
+
+        try!(add_missing_columns(conn));
+
+        let create_offers_provider_id = conn.execute(
+            "CREATE INDEX IF NOT EXISTS offers_provider_id ON offers (provider_id)", &[]);
+
+        try!(create_offers_provider_id.map_err(|err| Error::ExecutingDbQuery(err.to_string())));
+
+        let create_leg_details = conn.execute(
+            "CREATE VIEW IF NOT EXISTS leg_details AS
+            SELECT
+                legs.*,
+                offer_slices.offer_id,
+                offer_slices.position AS slice_position,
+                segments.carrier,
+                segments.number,
+                segments.cabin,
+                origins.name AS origin_name,
+                destinations.name AS destination_name,
+                carriers.name AS carrier_name,
+                aircraft.name AS aircraft_name
+            FROM legs
+            JOIN segments ON segments.id = legs.segment_id
+            JOIN offer_slices ON offer_slices.id = segments.slice_id
+            LEFT JOIN airports AS origins ON origins.code = legs.origin
+            LEFT JOIN airports AS destinations ON destinations.code = legs.destination
+            LEFT JOIN carriers ON carriers.code = segments.carrier
+            LEFT JOIN aircraft ON aircraft.code = legs.aircraft", &[]);
+
+        try!(create_leg_details.map_err(|err| Error::ExecutingDbQuery(err.to_string())));
 
         Ok(())
     }
 
-    pub fn db_reset(&self) {
-        remove_file(DEFAULT_DB_PATH).unwrap_or(());
+    pub fn db_reset(location: &Location) {
+        remove_file(&location.path).unwrap_or(());
+    }
+
+    pub fn requests(&self, watch: &Watch) -> Vec<Request> {
+        watch.requests(&self.sale_country)
+    }
+
+    pub fn duration_per_request(&self, watch: &Watch) -> Result<Duration, Error> {
+        if self.request_sets_per_day(watch) == 0 { return Err(Error::NoTripsOrDates) }
+
+        // whole seconds, or the daemon could wake up just before a run and run it twice
+        let seconds = Duration::hours(24).num_seconds() / (self.request_sets_per_day(watch) as i64);
+
+        Ok(Duration::seconds(seconds))
     }
 
-    pub fn requests(&self) -> Vec<Request> {
-        let mut requests: Vec<Request> = (0..self.total_calls()).map(|_| {
-            Request::new(&self.request_name, &self.sale_country)
-        }).collect();
+    /// All watches sharing the earliest `next_run_at`, skipping idle ones.
+    pub fn next_watches(&self) -> Result<Vec<&Watch>, Error> {
+        let mut next_run_at: Option<Timespec> = None;
+        let mut watches = vec!();
+
+        for watch in &self.watches {
+            let run_at = match self.next_run_at(watch) {
+                Ok(run_at) => run_at.to_timespec(),
+                Err(_) => continue
+            };
+
+            if next_run_at.is_none() || run_at < next_run_at.unwrap() {
+                next_run_at = Some(run_at);
+                watches.clear();
+            }
 
-        for trip in &self.trips {
-            let mut dates_iterator = trip.dates.iter().cycle();
-            for request in &mut requests {
-                request.add_trip(&trip.to, &trip.from, dates_iterator.next().unwrap(), 0);
+            if next_run_at == Some(run_at) {
+                watches.push(watch);
             }
         }
 
-        requests
-    }
+        if watches.is_empty() { return Err(Error::NoTripsOrDates) }
 
-    pub fn duration_per_request(&self) -> Result<Duration, Error> {
-        if self.request_sets_per_day() == 0 { return Err(Error::NoTripsOrDates) }
+        Ok(watches)
+    }
 
-        Ok(Duration::hours(24) / (self.request_sets_per_day() as i32))
+    pub fn idle_watches(&self) -> Vec<&Watch> {
+        self.watches.iter().filter(|watch| self.request_sets_per_day(watch) == 0).collect()
     }
 
-    pub fn next_run_at(&self) -> Result<Tm, Error> {
-        let duration_per_request = try!(self.duration_per_request());
+    pub fn next_run_at(&self, watch: &Watch) -> Result<Tm, Error> {
+        let duration_per_request = try!(self.duration_per_request(watch));
         let now = now();
         let mut next_run_at = midnight();
 
@@ -144,8 +673,21 @@ impl Session {
         Ok(next_run_at)
     }
 
-    pub fn next_run_seconds(&self) -> Result<u64, Error> {
-        let next_run_at = try!(self.next_run_at());
+    pub fn next_runs_at(&self, watch: &Watch, count: usize) -> Result<Vec<Tm>, Error> {
+        let duration_per_request = try!(self.duration_per_request(watch));
+        let mut run_at = try!(self.next_run_at(watch));
+        let mut runs = vec!();
+
+        for _ in 0..count {
+            runs.push(run_at);
+            run_at = run_at + duration_per_request;
+        }
+
+        Ok(runs)
+    }
+
+    pub fn next_run_seconds(&self, watch: &Watch) -> Result<u64, Error> {
+        let next_run_at = try!(self.next_run_at(watch));
         let next_run_seconds = next_run_at.to_timespec().sec - now().to_timespec().sec;
 
         match next_run_seconds.is_positive() {
@@ -154,18 +696,142 @@ impl Session {
         }
     }
 
-    pub fn next_run_duration(&self) -> Result<StdDuration, Error> {
-        let next_run_seconds = try!(self.next_run_seconds());
+    pub fn next_run_duration(&self, watch: &Watch) -> Result<StdDuration, Error> {
+        let next_run_seconds = try!(self.next_run_seconds(watch));
 
         Ok(StdDuration::from_secs(next_run_seconds))
     }
 }
 
+/// Decoding stops at the first problem, without telling which watch or trip it belongs to, so
+/// decode them one by one to find the full key.
+fn decode_diagnostic(table: &Table, error: DecodeError) -> Diagnostic {
+    let watches = match table.get("watches") {
+        Some(&Value::Array(ref watches)) => watches.clone(),
+        _ => vec!()
+    };
+
+    for (watch_index, watch) in watches.into_iter().enumerate() {
+        let watch_key = format!("watches[{}]", watch_index);
+
+        if let Err(watch_error) = Watch::decode(&mut Decoder::new(watch.clone())) {
+            let trips = match watch.lookup("trips") {
+                Some(&Value::Array(ref trips)) => trips.clone(),
+                _ => vec!()
+            };
+
+            for (trip_index, trip) in trips.into_iter().enumerate() {
+                if let Err(trip_error) = Trip::decode(&mut Decoder::new(trip)) {
+                    return diagnostic_for(&format!("{}.trips[{}]", watch_key, trip_index), trip_error)
+                }
+            }
+
+            return diagnostic_for(&watch_key, watch_error)
+        }
+    }
+
+    diagnostic_for("", error)
+}
+
+fn diagnostic_for(key: &str, error: DecodeError) -> Diagnostic {
+    let key = match error.field {
+        Some(ref field) => child_key(key, field),
+        None => key.to_string()
+    };
+
+    let message = error.to_string();
+    let message = message.split(" for the key").next().unwrap_or("");
+
+    Diagnostic::error(&key, message)
+}
+
+/// The decoder leaves behind the keys no field uses. It can't tell which element of an array they
+/// came from though, so watches and trips are decoded again one by one.
+fn unknown_keys(table: &Table, leftover: Option<Value>) -> Vec<Diagnostic> {
+    let mut diagnostics = vec!();
+    leftover_keys("", leftover, "watches", &mut diagnostics);
+
+    if let Some(&Value::Array(ref watches)) = table.get("watches") {
+        for (watch_index, watch) in watches.iter().enumerate() {
+            let watch_key = format!("watches[{}]", watch_index);
+            leftover_keys(&watch_key, leftover_of::<Watch>(watch), "trips", &mut diagnostics);
+
+            if let Some(&Value::Array(ref trips)) = watch.lookup("trips") {
+                for (trip_index, trip) in trips.iter().enumerate() {
+                    let trip_key = format!("{}.trips[{}]", watch_key, trip_index);
+                    leftover_keys(&trip_key, leftover_of::<Trip>(trip), "", &mut diagnostics);
+                }
+            }
+        }
+    }
+
+    diagnostics
+}
+
+fn leftover_of<T: Decodable>(value: &Value) -> Option<Value> {
+    let mut decoder = Decoder::new(value.clone());
+
+    match T::decode(&mut decoder) {
+        Ok(_) => decoder.toml,
+        Err(_) => None
+    }
+}
+
+fn leftover_keys(key: &str, leftover: Option<Value>, skipped: &str, diagnostics: &mut Vec<Diagnostic>) {
+    if let Some(Value::Table(table)) = leftover {
+        for (name, value) in table {
+            if name == skipped { continue }
+
+            match value {
+                Value::Table(ref nested) if !nested.is_empty() => leftover_keys(&child_key(key, &name), Some(value.clone()), "", diagnostics),
+                _ => diagnostics.push(Diagnostic::error(&child_key(key, &name), "is not a known key"))
+            }
+        }
+    }
+}
+
+fn locate_all(source: &str, diagnostics: Vec<Diagnostic>) -> Vec<Diagnostic> {
+    diagnostics.into_iter().map(|mut diagnostic| {
+        diagnostic.line = locate(source, &diagnostic.key);
+        diagnostic
+    }).collect()
+}
+
+fn add_missing_columns(conn: &Connection) -> Result<(), Error> {
+    for &(table, column) in ADDED_COLUMNS.iter() {
+        let name = column.split(' ').next().unwrap_or("");
+
+        if !try!(has_column(conn, table, name)) {
+            try!(conn.execute(&format!("ALTER TABLE {} ADD COLUMN {}", table, column), &[])
+                .map_err(|err| Error::ExecutingDbQuery(err.to_string())));
+        }
+    }
+
+    Ok(())
+}
+
+/// Whether the table has the column, or doesn't exist at all.
+fn has_column(conn: &Connection, table: &str, column: &str) -> Result<bool, Error> {
+    let mut sql = try!(conn.prepare(&format!("PRAGMA table_info({})", table))
+        .map_err(|err| Error::PreparingDbQuery(err.to_string())));
+
+    let rows = try!(sql.query(&[]).map_err(|err| Error::ExecutingDbQuery(err.to_string())));
+
+    let mut columns = vec!();
+    for row in rows {
+        let data = try!(row.map_err(|err| Error::UnwrappingDbRow(err.to_string())));
+        columns.push(data.get::<String>(1));
+    }
+
+    Ok(columns.is_empty() || columns.iter().any(|name| name == column))
+}
+
 pub fn midnight() -> Tm {
     let mut midnight = now();
     midnight.tm_hour = 0;
     midnight.tm_min = 0;
     midnight.tm_sec = 0;
+    midnight.tm_nsec = 0;
 
     midnight
 }
@@ -0,0 +1,276 @@
+use flights::{Request, Passengers};
+use diagnostics::{Diagnostic, child_key, is_airport_code};
+use providers::{DEFAULT_PROVIDER, PROVIDERS};
+use dates;
+use money;
+use Error;
+
+const CABINS: [&'static str; 4] = ["COACH", "PREMIUM_COACH", "BUSINESS", "FIRST"];
+
+#[derive(RustcDecodable, PartialEq)]
+pub struct Watch {
+    pub name: String,
+    pub provider: Option<String>,
+    pub share: Option<usize>,
+    pub passengers: Option<PassengerCounts>,
+    pub max_price: Option<String>,
+    pub refundable: Option<bool>,
+    pub solutions: Option<u8>,
+    pub trips: Vec<Trip>
+}
+
+#[derive(RustcDecodable, PartialEq)]
+pub struct PassengerCounts {
+    pub adults: Option<u8>,
+    pub children: Option<u8>,
+    pub seniors: Option<u8>,
+    pub infants_in_lap: Option<u8>,
+    pub infants_in_seat: Option<u8>
+}
+
+#[derive(RustcDecodable, PartialEq)]
+pub struct Trip {
+    pub from: String,
+    pub to: String,
+    pub dates: Option<Vec<String>>,
+    pub from_date: Option<String>,
+    pub to_date: Option<String>,
+    pub weekdays: Option<Vec<String>>,
+    pub flex_days: Option<i64>,
+    pub cabin: Option<String>,
+    pub max_connection_duration: Option<u32>,
+    pub max_stops: Option<u8>,
+    pub permitted_connections: Option<Vec<String>>,
+    pub prohibited_connections: Option<Vec<String>>,
+    pub min_stay: Option<i64>,
+    pub max_stay: Option<i64>
+}
+
+#[derive(Clone)]
+struct Departure<'a> {
+    trip: &'a Trip,
+    origin: &'a str,
+    destination: &'a str,
+    date: String
+}
+
+impl Watch {
+    pub fn check(&self, key: &str) -> Vec<Diagnostic> {
+        let mut diagnostics = vec!();
+
+        if self.name.is_empty() {
+            diagnostics.push(Diagnostic::error(&child_key(key, "name"), "can't be empty"));
+        }
+
+        if !PROVIDERS.contains(&self.provider()) {
+            diagnostics.push(Diagnostic::error(&child_key(key, "provider"), &format!("{} is not one of {}", self.provider(), PROVIDERS.join(", "))));
+        }
+
+        if self.share == Some(0) {
+            diagnostics.push(Diagnostic::error(&child_key(key, "share"), "has to be greater than 0"));
+        }
+
+        if let Err(error) = self.passengers().validate() {
+            diagnostics.push(Diagnostic::from_error(&child_key(key, "passengers"), error));
+        }
+
+        if let Some(ref max_price) = self.max_price {
+            if money::parse(max_price).is_err() {
+                diagnostics.push(Diagnostic::error(&child_key(key, "max_price"), &format!("{} is not an amount like EUR400", max_price)));
+            }
+        }
+
+        if self.solutions == Some(0) {
+            diagnostics.push(Diagnostic::error(&child_key(key, "solutions"), "has to be greater than 0"));
+        }
+
+        if self.trips.is_empty() {
+            diagnostics.push(Diagnostic::error(&child_key(key, "trips"), "at least one trip is required"));
+        }
+
+        for (index, trip) in self.trips.iter().enumerate() {
+            diagnostics.extend(trip.check(&format!("{}[{}]", child_key(key, "trips"), index)));
+        }
+
+        diagnostics
+    }
+
+    pub fn provider(&self) -> &str {
+        self.provider.as_ref().map(|provider| provider.as_str()).unwrap_or(DEFAULT_PROVIDER)
+    }
+
+    pub fn share(&self) -> usize {
+        self.share.unwrap_or(1)
+    }
+
+    pub fn passengers(&self) -> Passengers {
+        match self.passengers {
+            Some(ref counts) => counts.to_passengers(),
+            None => Passengers::single_adult()
+        }
+    }
+
+    pub fn total_calls(&self) -> usize {
+        self.combinations().len()
+    }
+
+    pub fn requests(&self, sale_country: &str) -> Vec<Request> {
+        let passengers = self.passengers();
+
+        self.combinations().iter().map(|combination| {
+            let mut request = Request::new(&self.name, sale_country, &passengers);
+
+            if let Some(ref max_price) = self.max_price { request.max_price(max_price); }
+            if let Some(refundable) = self.refundable { request.refundable(refundable); }
+            if let Some(solutions) = self.solutions { request.solutions(solutions); }
+
+            for departure in combination {
+                let trip = departure.trip;
+                request.add_trip(departure.origin, departure.destination, &departure.date, trip.max_stops.unwrap_or(0));
+
+                if let Some(ref cabin) = trip.cabin { request.preferred_cabin(cabin); }
+                if let Some(minutes) = trip.max_connection_duration { request.max_connection_duration(minutes); }
+                if let Some(ref airports) = trip.permitted_connections { request.permitted_connection_points(airports); }
+                if let Some(ref airports) = trip.prohibited_connections { request.prohibited_connection_points(airports); }
+            }
+
+            request
+        }).collect()
+    }
+
+    /// Skips combinations where a trip would depart before the previous one.
+    fn combinations(&self) -> Vec<Vec<Departure>> {
+        if self.trips.is_empty() { return vec!() }
+
+        let mut combinations: Vec<Vec<Departure>> = vec!(vec!());
+
+        for trip in &self.trips {
+            let options = trip.departures();
+            let mut extended_combinations = vec!();
+
+            for combination in &combinations {
+                for option in &options {
+                    if let (Some(previous), Some(next)) = (combination.last(), option.first()) {
+                        if next.date < previous.date { continue }
+                    }
+
+                    let mut extended_combination = combination.clone();
+                    extended_combination.extend(option.iter().cloned());
+                    extended_combinations.push(extended_combination);
+                }
+            }
+
+            combinations = extended_combinations;
+        }
+
+        combinations
+    }
+}
+
+impl Trip {
+    pub fn check(&self, key: &str) -> Vec<Diagnostic> {
+        let mut diagnostics = vec!();
+
+        for &(name, code) in &[("from", &self.from), ("to", &self.to)] {
+            if !is_airport_code(code) {
+                diagnostics.push(Diagnostic::error(&child_key(key, name), &format!("{} is not a three-letter IATA code", code)));
+            }
+        }
+
+        for &(name, airports) in &[("permitted_connections", &self.permitted_connections), ("prohibited_connections", &self.prohibited_connections)] {
+            for code in airports.iter().flat_map(|airports| airports.iter()) {
+                if !is_airport_code(code) {
+                    diagnostics.push(Diagnostic::error(&child_key(key, name), &format!("{} is not a three-letter IATA code", code)));
+                }
+            }
+        }
+
+        match self.expand_dates() {
+            Ok(ref dates) if dates.is_empty() => diagnostics.push(Diagnostic::warning(&child_key(key, "dates"), "no dates left to search")),
+            Ok(_) => {},
+            Err(error) => diagnostics.push(Diagnostic::from_error(&child_key(key, "dates"), error))
+        }
+
+        if let Err(error) = self.stay() {
+            diagnostics.push(Diagnostic::from_error(&child_key(key, "min_stay"), error));
+        }
+
+        if let Some(ref cabin) = self.cabin {
+            if !CABINS.contains(&cabin.as_str()) {
+                diagnostics.push(Diagnostic::error(&child_key(key, "cabin"), &format!("{} is not one of {}", cabin, CABINS.join(", "))));
+            }
+        }
+
+        diagnostics
+    }
+
+    pub fn expand_dates(&self) -> Result<Vec<String>, Error> {
+        let mut expanded_dates = self.dates.clone().unwrap_or(vec!());
+
+        match (&self.from_date, &self.to_date) {
+            (&Some(ref from), &Some(ref to)) => expanded_dates.extend(try!(dates::range(from, to))),
+            (&None, &None) => {},
+            _ => return Err(Error::InvalidDates("from_date and to_date have to be used together".to_string()))
+        }
+
+        if let Some(ref weekdays) = self.weekdays {
+            let mut parsed_weekdays = vec!();
+            for weekday in weekdays {
+                parsed_weekdays.push(try!(dates::parse_weekday(weekday)));
+            }
+
+            expanded_dates = try!(dates::on_weekdays(&expanded_dates, &parsed_weekdays));
+        }
+
+        dates::flex(&expanded_dates, self.flex_days.unwrap_or(0))
+    }
+
+    pub fn stay(&self) -> Result<Option<(i64, i64)>, Error> {
+        let (min_stay, max_stay) = match (self.min_stay, self.max_stay) {
+            (None, None) => return Ok(None),
+            (Some(min_stay), None) => (min_stay, min_stay),
+            (None, Some(max_stay)) => (0, max_stay),
+            (Some(min_stay), Some(max_stay)) => (min_stay, max_stay)
+        };
+
+        if min_stay < 0 || max_stay < min_stay || max_stay > dates::MAX_RANGE_DAYS {
+            return Err(Error::InvalidDates(format!("a stay of {} to {} days is not possible", min_stay, max_stay)))
+        }
+
+        Ok(Some((min_stay, max_stay)))
+    }
+
+    fn departures(&self) -> Vec<Vec<Departure>> {
+        let mut departures = vec!();
+
+        for date in self.expand_dates().unwrap_or(vec!()) {
+            let outbound = Departure { trip: self, origin: &self.from, destination: &self.to, date: date.clone() };
+
+            match self.stay().unwrap_or(None) {
+                Some((min_stay, max_stay)) => {
+                    for stay in min_stay..(max_stay + 1) {
+                        if let Ok(return_date) = dates::add_days(&date, stay) {
+                            let inbound = Departure { trip: self, origin: &self.to, destination: &self.from, date: return_date };
+                            departures.push(vec!(outbound.clone(), inbound));
+                        }
+                    }
+                },
+                None => departures.push(vec!(outbound))
+            }
+        }
+
+        departures
+    }
+}
+
+impl PassengerCounts {
+    pub fn to_passengers(&self) -> Passengers {
+        Passengers {
+            adults: self.adults.unwrap_or(0),
+            children: self.children.unwrap_or(0),
+            seniors: self.seniors.unwrap_or(0),
+            infants_in_lap: self.infants_in_lap.unwrap_or(0),
+            infants_in_seat: self.infants_in_seat.unwrap_or(0)
+        }
+    }
+}
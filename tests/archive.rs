@@ -0,0 +1,61 @@
+extern crate flycheap;
+extern crate rusqlite;
+
+use rusqlite::Connection;
+
+use flycheap::Error;
+use flycheap::flights::{Archive, Offer, Request, Store};
+use flycheap::flights::search::RawResponse;
+use flycheap::providers::FareProvider;
+
+mod common;
+
+fn archive(conn: &Connection, provider: &str) -> Archive {
+    let replay = common::replay();
+    let response = RawResponse { query: "roundtrip".to_string(), body: replay.execute("roundtrip").unwrap() };
+
+    let mut request = common::request();
+    let request_id = Store::new(conn, true).save_request(&mut request, provider, &response).unwrap();
+
+    Archive::load(conn, Some(request_id)).unwrap().remove(0)
+}
+
+#[test]
+fn test_reparse_replaces_the_offers_of_the_request() {
+    let conn = common::db();
+    let archive = archive(&conn, "replay");
+
+    let offers = archive.reparse(&conn).unwrap();
+    assert_eq!(4, offers.len());
+
+    archive.reparse(&conn).unwrap();
+    let stored = Offer::find_by_provider_id(&conn, offers[0].provider_id.as_ref().unwrap()).unwrap();
+
+    assert_eq!(1, stored.len());
+    assert_eq!(Some(archive.request_id), stored[0].request_id);
+    assert_eq!(194.56, stored[0].total_price);
+    assert_eq!(Some("bwrfa5nntkafbK6iE0NcY4".to_string()), Request::find(&conn, archive.request_id).unwrap().unwrap().provider_request_id);
+}
+
+#[test]
+fn test_reparse_unknown_provider() {
+    let conn = common::db();
+
+    match archive(&conn, "amadeus").reparse(&conn) {
+        Err(Error::UnknownProvider(ref name)) => assert_eq!("amadeus", name),
+        other => panic!("expected an unknown provider, got {:?}", other.map(|offers| offers.len()))
+    }
+}
+
+#[test]
+fn test_failed_reparse_keeps_the_offers() {
+    let conn = common::db();
+    let archive = archive(&conn, "replay");
+    let offers = archive.reparse(&conn).unwrap();
+
+    conn.execute_batch("CREATE TEMP TRIGGER reject_offers BEFORE INSERT ON offers BEGIN SELECT RAISE(ABORT, 'disk full'); END;").unwrap();
+    assert!(archive.reparse(&conn).is_err());
+
+    let stored = Offer::find_by_provider_id(&conn, offers[0].provider_id.as_ref().unwrap()).unwrap();
+    assert_eq!(1, stored.len());
+}
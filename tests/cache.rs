@@ -0,0 +1,68 @@
+extern crate flycheap;
+extern crate rusqlite;
+extern crate time;
+
+use flycheap::cache::Cache;
+use flycheap::flights::{Archive, Store};
+use flycheap::flights::search::RawResponse;
+
+mod common;
+
+fn response() -> RawResponse {
+    RawResponse { query: "TXL-OTP-2030-03-28".to_string(), body: "{}".to_string() }
+}
+
+#[test]
+fn test_get_returns_stored_response() {
+    let conn = common::db();
+    let cache = Cache::new(60);
+
+    assert!(cache.get(&conn, "replay:search").unwrap().is_none());
+
+    cache.put(&conn, "replay:search", &response()).unwrap();
+    let cached = cache.get(&conn, "replay:search").unwrap().unwrap();
+
+    assert_eq!(cached.query, "TXL-OTP-2030-03-28");
+    assert_eq!(cached.body, "{}");
+}
+
+#[test]
+fn test_disabled_cache() {
+    let conn = common::db();
+    let cache = Cache::new(0);
+
+    cache.put(&conn, "replay:search", &response()).unwrap();
+
+    assert!(cache.get(&conn, "replay:search").unwrap().is_none());
+}
+
+#[test]
+fn test_cache_hits_are_not_archived_again() {
+    let conn = common::db();
+    let store = Store::new(&conn, true);
+
+    let mut request = common::request();
+    store.save_request(&mut request, "replay", &response()).unwrap();
+
+    let mut cached = common::request();
+    cached.cache_hit = true;
+    store.save_request(&mut cached, "replay", &response()).unwrap();
+
+    assert_eq!(1, Archive::load(&conn, None).unwrap().len());
+}
+
+#[test]
+fn test_put_deletes_expired_responses() {
+    let conn = common::db();
+    let cache = Cache::new(60);
+
+    cache.put(&conn, "replay:old", &response()).unwrap();
+    let two_hours_ago = (time::now_utc() - time::Duration::hours(2)).to_timespec();
+    conn.execute("UPDATE cache SET created_at = ?", &[&two_hours_ago]).unwrap();
+
+    cache.put(&conn, "replay:new", &response()).unwrap();
+
+    let count: i64 = conn.query_row("SELECT COUNT(*) FROM cache", &[], |row| row.get(0)).unwrap();
+    assert_eq!(1, count);
+    assert!(cache.get(&conn, "replay:new").unwrap().is_some());
+}
@@ -0,0 +1,77 @@
+#![allow(dead_code)]
+
+use rusqlite::Connection;
+
+use flycheap::Session;
+use flycheap::flights::{Request, Passengers};
+use flycheap::providers::replay::{Replay, ReplayConfig};
+use flycheap::watch::{Watch, Trip};
+
+pub fn replay_config() -> ReplayConfig {
+    ReplayConfig { dir: "tests/mocks".to_string(), latency_ms: None, fail_every: None, drift: None }
+}
+
+pub fn replay() -> Replay {
+    Replay::new(&replay_config())
+}
+
+/// An in-memory database with every table set up.
+pub fn db() -> Connection {
+    let conn = Connection::open_in_memory().unwrap();
+    Session::db_setup(&conn).unwrap();
+
+    conn
+}
+
+pub fn request() -> Request {
+    Request::new("txl_to_otp", "DE", &Passengers::single_adult())
+}
+
+pub fn trip(from: &str, to: &str, dates: &[&str]) -> Trip {
+    Trip {
+        from: from.to_string(),
+        to: to.to_string(),
+        dates: Some(dates.iter().map(|date| date.to_string()).collect()),
+        from_date: None,
+        to_date: None,
+        weekdays: None,
+        flex_days: None,
+        cabin: None,
+        max_connection_duration: None,
+        max_stops: None,
+        permitted_connections: None,
+        prohibited_connections: None,
+        min_stay: None,
+        max_stay: None
+    }
+}
+
+pub fn watch(name: &str, trips: Vec<Trip>) -> Watch {
+    Watch {
+        name: name.to_string(),
+        provider: None,
+        share: None,
+        passengers: None,
+        max_price: None,
+        refundable: None,
+        solutions: None,
+        trips: trips
+    }
+}
+
+pub fn session(requests_per_day: usize, watches: Vec<Watch>) -> Session {
+    Session {
+        email: None,
+        google_api_key: None,
+        google_api_key_from: None,
+        requests_per_day: requests_per_day,
+        sale_country: "DE".to_string(),
+        replay: None,
+        archive: None,
+        cache_ttl_minutes: None,
+        retry: None,
+        http: None,
+        concurrency: None,
+        watches: watches
+    }
+}
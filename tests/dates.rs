@@ -0,0 +1,40 @@
+extern crate flycheap;
+
+use flycheap::dates;
+
+#[test]
+fn test_parse_date_rejects_rolled_over_days() {
+    assert!(dates::parse_date("2016-02-29").is_ok());
+    assert!(dates::parse_date("2016-02-30").is_err());
+    assert!(dates::parse_date("28.03.2016").is_err());
+}
+
+#[test]
+fn test_range_includes_both_ends() {
+    let range = dates::range("2016-02-27", "2016-03-01").unwrap();
+
+    assert_eq!(vec!("2016-02-27", "2016-02-28", "2016-02-29", "2016-03-01"), range);
+    assert!(dates::range("2016-03-01", "2016-02-27").is_err());
+}
+
+#[test]
+fn test_on_weekdays() {
+    let range = dates::range("2016-05-01", "2016-05-31").unwrap();
+    let fridays = dates::on_weekdays(&range, &[dates::parse_weekday("Fri").unwrap()]).unwrap();
+
+    assert_eq!(vec!("2016-05-06", "2016-05-13", "2016-05-20", "2016-05-27"), fridays);
+}
+
+#[test]
+fn test_flex_widens_and_deduplicates() {
+    let dates = vec!("2999-03-28".to_string(), "2999-03-29".to_string());
+
+    assert_eq!(vec!("2999-03-27", "2999-03-28", "2999-03-29", "2999-03-30"), dates::flex(&dates, 1).unwrap());
+}
+
+#[test]
+fn test_flex_drops_past_dates() {
+    let dates = vec!("2016-03-28".to_string(), "2999-03-28".to_string());
+
+    assert_eq!(vec!("2999-03-28"), dates::flex(&dates, 0).unwrap());
+}
@@ -0,0 +1,50 @@
+extern crate flycheap;
+extern crate rusqlite;
+
+use rusqlite::Connection;
+
+use flycheap::Session;
+use flycheap::flights::{Request, Offer};
+
+mod common;
+
+#[test]
+fn test_db_setup_adds_missing_columns_to_old_databases() {
+    let conn = Connection::open_in_memory().unwrap();
+    conn.execute("CREATE TABLE requests (id INTEGER PRIMARY KEY, name TEXT NOT NULL, created_at INTEGER NOT NULL)", &[]).unwrap();
+    conn.execute("CREATE TABLE offers (id INTEGER PRIMARY KEY, request_id INTEGER NOT NULL, currency TEXT NOT NULL,
+        base_price REAL NOT NULL, sale_price REAL NOT NULL, tax_price REAL NOT NULL, total_price REAL NOT NULL,
+        latest_ticketing_at INTEGER NOT NULL, refundable INTEGER NOT NULL)", &[]).unwrap();
+
+    let mut request = common::request();
+    conn.execute("INSERT INTO requests (name, created_at) VALUES (?, ?)", &[&request.name, &request.created_at]).unwrap();
+
+    Session::db_setup(&conn).unwrap();
+    Session::db_setup(&conn).unwrap();
+
+    request.create(&conn).unwrap();
+
+    assert_eq!(Some(1), Request::find(&conn, 1).unwrap().map(|request| request.passengers.adults));
+    assert!(Offer::find_by_provider_id(&conn, "option").unwrap().is_empty());
+}
+
+#[test]
+fn test_request_filters_are_stored_with_the_request() {
+    let conn = common::db();
+
+    let mut request = common::request();
+    request.max_price("EUR400").refundable(true).solutions(20);
+    request.add_trip("TXL", "OTP", "2999-03-28", 0).preferred_cabin("BUSINESS").max_connection_duration(180);
+    request.create(&conn).unwrap();
+
+    let stored = Request::find(&conn, request.id.unwrap()).unwrap().unwrap();
+    assert_eq!(Some("EUR400".to_string()), stored.max_price);
+    assert_eq!(Some(true), stored.refundable);
+    assert_eq!(Some(20), stored.solutions);
+
+    let (cabin, minutes): (String, i64) = conn.query_row("SELECT preferred_cabin, max_connection_duration FROM request_slices", &[], |row| {
+        (row.get(0), row.get(1))
+    }).unwrap();
+    assert_eq!("BUSINESS", cabin);
+    assert_eq!(180, minutes);
+}
@@ -0,0 +1,152 @@
+extern crate flycheap;
+
+use flycheap::Session;
+use flycheap::diagnostics::locate;
+
+const CONFIG: &'static str = r#"google_api_key = "KEY"
+requests_per_day = 50
+
+[[watches]]
+name = "txl_to_otp"
+
+[watches.passengers]
+adults = 2
+
+[[watches.trips]]
+from = "TXL"
+to = "OTP"
+
+[[watches.trips]]
+from = "OTP"
+to = "TXL"
+
+[[watches]]
+name = "muc_to_jfk"
+
+[[watches.trips]]
+from = "Munich"
+"#;
+
+#[test]
+fn test_locate_top_level_key() {
+    assert_eq!(Some(2), locate(CONFIG, "requests_per_day"));
+}
+
+#[test]
+fn test_locate_nested_keys() {
+    assert_eq!(Some(8), locate(CONFIG, "watches[0].passengers.adults"));
+    assert_eq!(Some(15), locate(CONFIG, "watches[0].trips[1].from"));
+    assert_eq!(Some(22), locate(CONFIG, "watches[1].trips[0].from"));
+}
+
+#[test]
+fn test_locate_missing_key_points_to_its_table() {
+    assert_eq!(Some(21), locate(CONFIG, "watches[1].trips[0].to"));
+    assert_eq!(Some(18), locate(CONFIG, "watches[1]"));
+}
+
+#[test]
+fn test_locate_unknown_table() {
+    assert_eq!(None, locate(CONFIG, "watches[2].name"));
+}
+
+#[test]
+fn test_check_rejects_configs_where_no_watch_runs() {
+    let config = r#"google_api_key = "KEY"
+requests_per_day = 50
+sale_country = "DE"
+
+[[watches]]
+name = "txl_to_otp"
+
+[[watches.trips]]
+from = "TXL"
+to = "OTP"
+dates = ["2000-03-28"]
+"#;
+
+    let (_, diagnostics) = Session::check(config);
+    let errors: Vec<_> = diagnostics.iter().filter(|diagnostic| diagnostic.is_error()).collect();
+
+    assert_eq!(1, errors.len());
+    assert_eq!("watches", errors[0].key);
+}
+
+#[test]
+fn test_check_warns_when_the_cache_outlives_the_runs() {
+    let config = r#"google_api_key = "KEY"
+requests_per_day = 50
+sale_country = "DE"
+cache_ttl_minutes = 60
+
+[[watches]]
+name = "txl_to_otp"
+
+[[watches.trips]]
+from = "TXL"
+to = "OTP"
+dates = ["2999-03-28", "2999-03-29"]
+"#;
+
+    let (_, diagnostics) = Session::check(config);
+    let warning = diagnostics.iter().find(|diagnostic| diagnostic.key == "cache_ttl_minutes").unwrap();
+
+    assert!(!warning.is_error());
+    assert_eq!(Some(4), warning.line);
+}
+
+#[test]
+fn test_check_reports_unknown_keys() {
+    let config = r#"google_api_key = "KEY"
+requests_per_day = 50
+sale_country = "DE"
+request_name = "legacy"
+
+[[watches]]
+name = "txl_to_otp"
+
+[watches.passengers]
+adult = 2
+
+[[watches.trips]]
+from = "TXL"
+to = "OTP"
+dates = ["2999-03-28"]
+
+[[watches.trips]]
+from = "OTP"
+to = "TXL"
+dates = ["2999-04-03"]
+max_stop = 1
+"#;
+
+    let (_, diagnostics) = Session::check(config);
+    let unknown: Vec<(&str, Option<usize>)> = diagnostics.iter()
+        .filter(|diagnostic| diagnostic.message == "is not a known key")
+        .map(|diagnostic| (diagnostic.key.as_str(), diagnostic.line))
+        .collect();
+
+    assert_eq!(vec!(("request_name", Some(4)), ("watches[0].passengers.adult", Some(10)), ("watches[0].trips[1].max_stop", Some(21))), unknown);
+}
+
+#[test]
+fn test_check_does_not_read_the_api_key() {
+    let config = r#"requests_per_day = 50
+sale_country = "DE"
+
+[google_api_key_from]
+command = "exit 1"
+
+[[watches]]
+name = "txl_to_otp"
+
+[[watches.trips]]
+from = "TXL"
+to = "OTP"
+dates = ["2999-03-28"]
+"#;
+
+    let (_, diagnostics) = Session::check(config);
+
+    assert!(diagnostics.is_empty());
+}
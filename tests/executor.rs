@@ -0,0 +1,127 @@
+extern crate flycheap;
+extern crate rusqlite;
+
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use flycheap::{executor, Error};
+use flycheap::flights::Request;
+use flycheap::flights::search::Fares;
+use flycheap::providers::FareProvider;
+use flycheap::providers::throttle::Throttle;
+
+mod common;
+
+fn request(date: &str) -> Request {
+    let mut request = common::request();
+    request.add_trip("TXL", "OTP", date, 0);
+
+    request
+}
+
+// Only answers once `expected` calls are waiting at the same time, so every request succeeds
+// only if they all run in parallel.
+struct Gathering {
+    expected: usize,
+    arrived: Mutex<usize>,
+    all_arrived: Condvar
+}
+
+impl FareProvider for Gathering {
+    fn name(&self) -> &str { "gathering" }
+
+    fn query(&self, request: &Request) -> Result<String, Error> {
+        Ok(request.slices[0].date.clone())
+    }
+
+    fn execute(&self, query: &str) -> Result<String, Error> {
+        let mut arrived = self.arrived.lock().unwrap();
+        *arrived += 1;
+        self.all_arrived.notify_all();
+
+        while *arrived < self.expected {
+            let (guard, wait) = self.all_arrived.wait_timeout(arrived, Duration::from_secs(5)).unwrap();
+            arrived = guard;
+
+            if wait.timed_out() { return Err(Error::SendingRequest("not run in parallel".to_string())) }
+        }
+
+        Ok(query.to_string())
+    }
+
+    fn decode(&self, _: &str) -> Result<Fares, Error> {
+        Err(Error::DecodingJson("the executor doesn't decode".to_string()))
+    }
+}
+
+// Keeps track of the most calls in flight at the same time.
+struct Counting {
+    in_flight: Mutex<usize>,
+    most_in_flight: Mutex<usize>
+}
+
+impl FareProvider for Counting {
+    fn name(&self) -> &str { "counting" }
+
+    fn query(&self, request: &Request) -> Result<String, Error> {
+        Ok(request.slices[0].date.clone())
+    }
+
+    fn execute(&self, query: &str) -> Result<String, Error> {
+        {
+            let mut in_flight = self.in_flight.lock().unwrap();
+            *in_flight += 1;
+
+            let mut most_in_flight = self.most_in_flight.lock().unwrap();
+            if *in_flight > *most_in_flight { *most_in_flight = *in_flight }
+        }
+
+        thread::sleep(Duration::from_millis(50));
+        *self.in_flight.lock().unwrap() -= 1;
+
+        Ok(query.to_string())
+    }
+
+    fn decode(&self, _: &str) -> Result<Fares, Error> {
+        Err(Error::DecodingJson("the executor doesn't decode".to_string()))
+    }
+}
+
+#[test]
+fn test_execute_returns_every_request() {
+    let provider = Gathering { expected: 4, arrived: Mutex::new(0), all_arrived: Condvar::new() };
+    let requests = vec!(request("2030-03-28"), request("2030-03-29"), request("2030-03-30"), request("2030-03-31"));
+
+    let mut dates: Vec<String> = executor::execute(requests, Arc::new(provider), 4).iter().map(|(request, result)| {
+        assert_eq!(result.unwrap().body, request.slices[0].date);
+
+        request.slices[0].date.clone()
+    }).collect();
+
+    dates.sort();
+
+    assert_eq!(dates, vec!("2030-03-28", "2030-03-29", "2030-03-30", "2030-03-31"));
+}
+
+#[test]
+fn test_execute_keeps_to_the_limit() {
+    let provider = Arc::new(Counting { in_flight: Mutex::new(0), most_in_flight: Mutex::new(0) });
+    let requests = vec!(request("2030-03-28"), request("2030-03-29"), request("2030-03-30"), request("2030-03-31"));
+
+    let results: Vec<_> = executor::execute(requests, provider.clone(), 2).iter().collect();
+
+    assert_eq!(4, results.len());
+    assert!(results.iter().all(|&(_, ref result)| result.is_ok()));
+    assert!(*provider.most_in_flight.lock().unwrap() <= 2);
+}
+
+#[test]
+fn test_throttle_spaces_out_calls() {
+    let throttle = Throttle::new(20.0);
+    let started_at = Instant::now();
+
+    for _ in 0..3 { throttle.wait(); }
+
+    assert!(started_at.elapsed() >= Duration::from_millis(100));
+}
@@ -0,0 +1,61 @@
+extern crate flycheap;
+extern crate hyper;
+extern crate time;
+
+use std::io;
+
+use time::{now_utc, Duration};
+
+use flycheap::Error;
+use flycheap::http::{parse_proxy, parse_retry_after, reading_error, sending_error};
+
+#[test]
+fn test_parse_proxy() {
+    assert_eq!(parse_proxy("http://proxy.example.com:3128").unwrap(), ("proxy.example.com".to_string(), 3128));
+    assert_eq!(parse_proxy("proxy.example.com:8080").unwrap(), ("proxy.example.com".to_string(), 8080));
+    assert_eq!(parse_proxy("http://proxy.example.com").unwrap(), ("proxy.example.com".to_string(), 80));
+}
+
+#[test]
+fn test_parse_invalid_proxy() {
+    assert!(parse_proxy("http://").is_err());
+}
+
+#[test]
+fn test_parse_retry_after() {
+    let in_two_minutes = (now_utc() + Duration::seconds(120)).strftime("%a, %d %b %Y %H:%M:%S GMT").unwrap().to_string();
+    let seconds = parse_retry_after(&in_two_minutes).unwrap();
+
+    assert_eq!(Some(120), parse_retry_after(" 120"));
+    assert!(seconds > 110 && seconds <= 120);
+    assert_eq!(Some(0), parse_retry_after("Wed, 21 Oct 2015 07:28:00 GMT"));
+    assert_eq!(None, parse_retry_after("soon"));
+}
+
+#[test]
+fn test_timeouts_are_told_apart() {
+    for kind in vec!(io::ErrorKind::TimedOut, io::ErrorKind::WouldBlock) {
+        match sending_error(hyper::Error::Io(io::Error::new(kind, "timed out"))) {
+            Error::Timeout(_) => {},
+            other => panic!("expected a timeout, got {:?}", other)
+        }
+
+        match reading_error(io::Error::new(kind, "timed out")) {
+            Error::Timeout(_) => {},
+            other => panic!("expected a timeout, got {:?}", other)
+        }
+    }
+}
+
+#[test]
+fn test_other_io_errors_are_not_timeouts() {
+    match sending_error(hyper::Error::Io(io::Error::new(io::ErrorKind::ConnectionRefused, "refused"))) {
+        Error::SendingRequest(_) => {},
+        other => panic!("expected a sending error, got {:?}", other)
+    }
+
+    match reading_error(io::Error::new(io::ErrorKind::ConnectionReset, "reset")) {
+        Error::ReadingResponse(_) => {},
+        other => panic!("expected a reading error, got {:?}", other)
+    }
+}
@@ -0,0 +1,31 @@
+extern crate flycheap;
+
+use flycheap::flights::Passengers;
+
+#[test]
+fn test_single_adult_is_valid() {
+    assert!(Passengers::single_adult().validate().is_ok());
+}
+
+#[test]
+fn test_children_only_is_invalid() {
+    let passengers = Passengers { adults: 0, children: 2, seniors: 0, infants_in_lap: 0, infants_in_seat: 0 };
+
+    assert!(passengers.validate().is_err());
+}
+
+#[test]
+fn test_more_than_nine_passengers_is_invalid() {
+    let passengers = Passengers { adults: 5, children: 5, seniors: 0, infants_in_lap: 0, infants_in_seat: 0 };
+
+    assert!(passengers.validate().is_err());
+}
+
+#[test]
+fn test_infants_in_lap_need_an_adult_or_senior_each() {
+    let valid = Passengers { adults: 1, children: 0, seniors: 1, infants_in_lap: 2, infants_in_seat: 0 };
+    let invalid = Passengers { adults: 1, children: 0, seniors: 0, infants_in_lap: 2, infants_in_seat: 0 };
+
+    assert!(valid.validate().is_ok());
+    assert!(invalid.validate().is_err());
+}
@@ -0,0 +1,38 @@
+extern crate flycheap;
+
+use std::env;
+use std::path::PathBuf;
+
+use flycheap::paths::{self, Source};
+
+// One test, as the environment is shared by every test of this file.
+#[test]
+fn test_config_resolution_order() {
+    env::set_var("FLYCHEAP_CONFIG", "/etc/flycheap.toml");
+    env::set_var("XDG_CONFIG_HOME", "/xdg");
+    env::set_var("HOME", "/home/traveler");
+
+    let flag = paths::config(Some("custom.toml"));
+    assert_eq!(PathBuf::from("custom.toml"), flag.path);
+    assert_eq!(Source::Flag("--config"), flag.source);
+
+    let environment = paths::config(None);
+    assert_eq!(PathBuf::from("/etc/flycheap.toml"), environment.path);
+    assert_eq!(Source::Environment("FLYCHEAP_CONFIG"), environment.source);
+
+    env::set_var("FLYCHEAP_CONFIG", "");
+    let xdg = paths::config(None);
+    assert_eq!(PathBuf::from("/xdg/flycheap/config.toml"), xdg.path);
+    assert_eq!(Source::Xdg("XDG_CONFIG_HOME"), xdg.source);
+
+    env::remove_var("XDG_CONFIG_HOME");
+    let home = paths::config(None);
+    assert_eq!(PathBuf::from("/home/traveler/.config/flycheap/config.toml"), home.path);
+    assert_eq!(Source::Home(".config"), home.source);
+    assert!(home.to_string().ends_with("(from ~/.config)"));
+
+    env::remove_var("HOME");
+    let working_directory = paths::db(None);
+    assert_eq!(PathBuf::from("data.sqlite"), working_directory.path);
+    assert_eq!(Source::WorkingDirectory, working_directory.source);
+}
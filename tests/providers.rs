@@ -0,0 +1,37 @@
+extern crate flycheap;
+extern crate rusqlite;
+
+use flycheap::Error;
+use flycheap::providers::Providers;
+
+mod common;
+
+use common::{session, trip, watch};
+
+#[test]
+fn test_providers_are_selected_per_watch() {
+    let mut easter = watch("easter", vec!(trip("TXL", "OTP", &["2999-03-28"])));
+    easter.provider = Some("replay".to_string());
+    let mut session = session(24, vec!(easter));
+    session.replay = Some(common::replay_config());
+
+    let providers = Providers::from_session(&session).unwrap();
+
+    assert_eq!("replay", providers.get("replay").unwrap().name());
+
+    match providers.get("qpx") {
+        Err(Error::UnknownProvider(name)) => assert_eq!("qpx", name),
+        _ => panic!("no watch uses qpx")
+    }
+}
+
+#[test]
+fn test_replay_provider_without_replay_table() {
+    let mut easter = watch("easter", vec!(trip("TXL", "OTP", &["2999-03-28"])));
+    easter.provider = Some("replay".to_string());
+
+    match Providers::from_session(&session(24, vec!(easter))) {
+        Err(Error::ReadingReplay(_)) => {},
+        _ => panic!("the replay provider needs a [replay] table")
+    }
+}
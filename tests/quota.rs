@@ -0,0 +1,31 @@
+extern crate flycheap;
+extern crate rusqlite;
+
+use flycheap::Error;
+use flycheap::quota::{self, Call};
+
+mod common;
+
+#[test]
+fn test_calls_count_against_remaining_budget() {
+    let conn = common::db();
+    assert_eq!(quota::remaining(&conn, 3).unwrap(), 3);
+
+    Call::new("replay", &Ok("{}".to_string())).create(&conn).unwrap();
+    Call::new("replay", &Err(Error::ResponseNotOk { status: 503, body: String::new(), retry_after: None })).create(&conn).unwrap();
+    Call::new("replay", &Err(Error::SendingRequest("timed out".to_string()))).create(&conn).unwrap();
+
+    assert_eq!(quota::calls_today(&conn).unwrap(), 3);
+    assert_eq!(quota::remaining(&conn, 3).unwrap(), 0);
+}
+
+#[test]
+fn test_call_outcome() {
+    let failed = Call::new("qpx", &Err(Error::ResponseNotOk { status: 403, body: String::new(), retry_after: None }));
+    assert_eq!(failed.status, Some(403));
+    assert!(failed.error.is_none());
+
+    let timed_out = Call::new("qpx", &Err(Error::SendingRequest("timed out".to_string())));
+    assert_eq!(timed_out.status, None);
+    assert!(timed_out.error.unwrap().contains("timed out"));
+}
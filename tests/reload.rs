@@ -0,0 +1,52 @@
+extern crate flycheap;
+extern crate rusqlite;
+
+use std::env;
+use std::fs::{File, remove_file};
+
+use flycheap::paths::{Location, Source};
+use flycheap::reload::ConfigWatcher;
+
+mod common;
+
+use common::{session, trip, watch};
+
+#[test]
+fn test_diff_lists_watches_and_settings_that_changed() {
+    let current = session(24, vec!(
+        watch("easter", vec!(trip("TXL", "OTP", &["2999-03-28"]))),
+        watch("summer", vec!(trip("MUC", "JFK", &["2999-07-01"])))
+    ));
+    let updated = session(48, vec!(
+        watch("summer", vec!(trip("MUC", "JFK", &["2999-07-02"]))),
+        watch("autumn", vec!(trip("TXL", "LIS", &["2999-10-01"])))
+    ));
+
+    assert_eq!(vec!("~ watch summer", "+ watch autumn", "- watch easter", "~ requests_per_day 24 -> 48"), current.diff(&updated));
+}
+
+#[test]
+fn test_diff_of_unchanged_sessions() {
+    let current = session(24, vec!(watch("easter", vec!(trip("TXL", "OTP", &["2999-03-28"])))));
+    let reloaded = session(24, vec!(watch("easter", vec!(trip("TXL", "OTP", &["2999-03-28"])))));
+
+    assert!(current.diff(&reloaded).is_empty());
+}
+
+#[test]
+fn test_watcher_notices_changes_once() {
+    let path = env::temp_dir().join("flycheap_reload_test.toml");
+    File::create(&path).unwrap();
+
+    let mut watcher = ConfigWatcher::new(&Location { path: path.clone(), source: Source::Flag("--config") });
+    assert!(!watcher.changed());
+
+    remove_file(&path).unwrap();
+    assert!(watcher.changed());
+    assert!(!watcher.changed());
+
+    File::create(&path).unwrap();
+    assert!(watcher.changed());
+
+    remove_file(&path).unwrap();
+}
@@ -0,0 +1,45 @@
+extern crate flycheap;
+extern crate rusqlite;
+
+use flycheap::providers::FareProvider;
+use flycheap::providers::replay::{Replay, ReplayConfig};
+
+mod common;
+
+fn replay(fail_every: Option<usize>) -> Replay {
+    Replay::new(&ReplayConfig { fail_every: fail_every, ..common::replay_config() })
+}
+
+#[test]
+fn test_execute_strips_status_line_and_headers() {
+    let body = replay(None).execute("roundtrip").unwrap();
+
+    assert!(body.starts_with("{"));
+    assert!(body.contains("bwrfa5nntkafbK6iE0NcY4"));
+}
+
+#[test]
+fn test_execute_without_recorded_response() {
+    assert!(replay(None).execute("TXL-OTP-2016-03-28").is_err());
+}
+
+#[test]
+fn test_execute_fails_every_nth_call() {
+    let provider = replay(Some(2));
+
+    assert!(provider.execute("roundtrip").is_ok());
+    assert!(provider.execute("roundtrip").is_err());
+    assert!(provider.execute("roundtrip").is_ok());
+}
+
+#[test]
+fn test_drift_is_applied_to_the_body_of_each_call() {
+    let provider = Replay::new(&ReplayConfig { drift: Some(0.5), ..common::replay_config() });
+
+    let first = provider.execute("roundtrip").unwrap();
+    let second = provider.execute("roundtrip").unwrap();
+
+    assert!(first.contains("\"EUR194.56\""));
+    assert!(second.contains("\"EUR291.84\""));
+    assert!(!second.contains("\"EUR194.56\""));
+}
@@ -0,0 +1,85 @@
+extern crate flycheap;
+extern crate rusqlite;
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use flycheap::Error;
+use flycheap::providers::FareProvider;
+use flycheap::quota::Allowance;
+use flycheap::providers::replay::{Replay, ReplayConfig};
+use flycheap::providers::retry::{Retrying, RetryPolicy};
+
+mod common;
+
+fn policy() -> RetryPolicy {
+    RetryPolicy { attempts: 3, base_delay: Duration::from_millis(10), max_delay: Duration::from_millis(50) }
+}
+
+fn retrying(fail_every: Option<usize>) -> Retrying {
+    let replay = Replay::new(&ReplayConfig { fail_every: fail_every, ..common::replay_config() });
+
+    Retrying::new(Box::new(replay), policy())
+}
+
+#[test]
+fn test_retries_server_errors() {
+    let provider = retrying(Some(1));
+
+    match provider.execute("roundtrip") {
+        Err(Error::ResponseNotOk { status: 503, .. }) => {},
+        other => panic!("expected a 503, got {:?}", other)
+    }
+
+    assert!(retrying(Some(2)).execute("roundtrip").is_ok());
+}
+
+#[test]
+fn test_does_not_retry_quota_errors() {
+    match retrying(None).execute("forbidden") {
+        Err(Error::ResponseNotOk { status: 403, ref body, .. }) => assert!(body.contains("dailyLimitExceeded")),
+        other => panic!("expected a 403, got {:?}", other)
+    }
+}
+
+#[test]
+fn test_retries_stop_when_the_allowance_is_used_up() {
+    let allowance = Arc::new(Allowance::new());
+    allowance.set(2);
+    let provider = retrying(Some(1)).limited(allowance.clone());
+
+    match provider.execute("roundtrip") {
+        Err(Error::QuotaUsedUp) => {},
+        other => panic!("expected the quota to be used up, got {:?}", other)
+    }
+
+    assert_eq!(2, provider.take_calls().len());
+    assert!(allowance.take().is_err());
+}
+
+#[test]
+fn test_delay() {
+    let policy = policy();
+    let unavailable = Error::ResponseNotOk { status: 503, body: String::new(), retry_after: None };
+    let rate_limited = Error::ResponseNotOk { status: 429, body: String::new(), retry_after: Some(1) };
+    let retry_now = Error::ResponseNotOk { status: 429, body: String::new(), retry_after: Some(0) };
+
+    assert!(policy.delay(&unavailable, 1).unwrap() <= Duration::from_millis(10));
+    assert!(policy.delay(&unavailable, 3).is_none());
+    assert!(policy.delay(&Error::SendingRequest("timed out".to_string()), 1).is_some());
+    assert!(policy.delay(&Error::ResponseNotOk { status: 400, body: String::new(), retry_after: None }, 1).is_none());
+
+    assert_eq!(Some(Duration::from_secs(0)), policy.delay(&retry_now, 1));
+    // Retry-After asks for more than max_delay
+    assert_eq!(None, policy.delay(&rate_limited, 1));
+}
+
+#[test]
+fn test_rate_limited_response_carries_retry_after() {
+    let replay = common::replay();
+
+    match replay.execute("rate_limited") {
+        Err(Error::ResponseNotOk { status: 429, retry_after: Some(120), .. }) => {},
+        other => panic!("expected a 429 with Retry-After, got {:?}", other)
+    }
+}
@@ -0,0 +1,141 @@
+extern crate flycheap;
+extern crate rusqlite;
+
+use flycheap::flights::{Request, SearchResult, Offer, Store};
+use flycheap::flights::search::RawResponse;
+use flycheap::providers::FareProvider;
+
+mod common;
+
+fn roundtrip() -> SearchResult {
+    let replay = common::replay();
+    let body = replay.execute("roundtrip").unwrap();
+
+    let mut request = common::request();
+    request.add_trip("TXL", "OTP", "2016-03-28", 0).add_trip("OTP", "TXL", "2016-04-03", 0);
+
+    SearchResult::decode(request, RawResponse { query: "roundtrip".to_string(), body: body }, &replay).unwrap()
+}
+
+#[test]
+fn test_search_result_offers() {
+    let result = roundtrip();
+
+    assert_eq!(Some("replay".to_string()), result.request.provider);
+    assert_eq!(None, result.request.id);
+    assert_eq!(4, result.offers.len());
+    assert_eq!("EUR", result.offers[0].currency);
+    assert_eq!(194.56, result.offers[0].total_price);
+    assert_eq!(None, result.offers[0].request_id);
+}
+
+#[test]
+fn test_search_result_reference_data() {
+    let reference = roundtrip().reference;
+
+    assert_eq!(2, reference.airports.len());
+    assert_eq!("Bucharest Henri Coanda", reference.airports[0].name);
+    assert_eq!("Berlin Tegel", reference.airports[1].name);
+    assert_eq!("AB", reference.carriers[0].code);
+    assert_eq!("Airbus A319", reference.aircraft[0].name);
+}
+
+#[test]
+fn test_search_result_slices() {
+    let result = roundtrip();
+    let slices = &result.offers[0].slices;
+
+    assert_eq!(2, slices.len());
+    assert_eq!(130, slices[0].duration);
+    assert_eq!(125, slices[1].duration);
+    assert_eq!("COACH", slices[0].segments[0].cabin);
+    assert_eq!("Z", slices[0].segments[0].booking_code);
+    assert_eq!("TXL", slices[0].origin().unwrap().origin);
+    assert_eq!("OTP", slices[1].origin().unwrap().origin);
+}
+
+#[test]
+fn test_search_result_legs_are_annotated() {
+    let result = roundtrip();
+    let segment = &result.offers[0].slices[0].segments[0];
+    let leg = &segment.legs[0];
+
+    assert_eq!(Some("Air Berlin PLC & Co. Luftverkehrs KG".to_string()), segment.carrier_name);
+    assert_eq!(Some("Berlin Tegel".to_string()), leg.origin_name);
+    assert_eq!(Some("Bucharest Henri Coanda".to_string()), leg.destination_name);
+    assert_eq!(Some("Airbus A319".to_string()), leg.aircraft_name);
+}
+
+#[test]
+fn test_search_result_pricing() {
+    let pricing = &roundtrip().offers[0].pricings[0];
+
+    assert_eq!("ADT", pricing.passenger_type);
+    assert_eq!(1, pricing.passengers);
+    assert_eq!(194.56, pricing.total_price);
+    assert_eq!(vec!("ZNN32RT", "ONNRT"), pricing.basis_codes());
+    assert!(pricing.fare_calculation.starts_with("BER AB BUH 13.61ZNN32RT"));
+}
+
+#[test]
+fn test_search_result_prices_every_passenger_type() {
+    let replay = common::replay();
+    let fares = replay.decode(&replay.execute("family").unwrap()).unwrap();
+    let offer = &fares.offers[0];
+
+    assert_eq!(2, offer.pricings.len());
+    assert_eq!("CNN", offer.pricings[1].passenger_type);
+    assert_eq!(2, offer.pricings[0].passengers);
+    assert_eq!(565.68, offer.total_price);
+    assert_eq!(198.0, offer.base_price);
+    assert_eq!(367.68, offer.tax_price);
+    assert_eq!("CNN", offer.taxes.last().unwrap().passenger_type);
+}
+
+#[test]
+fn test_search_result_counts_stops() {
+    let replay = common::replay();
+    let fares = replay.decode(&replay.execute("connection").unwrap()).unwrap();
+
+    assert_eq!(0, roundtrip().offers[0].stops);
+    assert_eq!(1, fares.offers[0].stops);
+    assert_eq!(2, fares.offers[0].slices[0].segments.len());
+}
+
+#[test]
+fn test_search_result_taxes() {
+    let result = roundtrip();
+    let tax = &result.offers[0].taxes[0];
+
+    assert_eq!("DE_001", tax.tax_id);
+    assert_eq!(Some("Germany Airport Security Charge".to_string()), tax.name);
+    assert_eq!(Some("GOVERNMENT".to_string()), tax.charge_type);
+    assert_eq!(6.87, tax.amount);
+    assert_eq!("EUR", tax.currency);
+}
+
+#[test]
+fn test_search_result_provider_ids() {
+    let result = roundtrip();
+
+    assert_eq!(Some("bwrfa5nntkafbK6iE0NcY4".to_string()), result.request.provider_request_id);
+    assert_eq!(Some("8zbPMZyxeX1O2vyrHtzPtA001".to_string()), result.offers[0].provider_id);
+}
+
+#[test]
+fn test_stored_offer_found_by_provider_id() {
+    let conn = common::db();
+
+    let mut result = roundtrip();
+    let request_id = Store::new(&conn, false).save(&mut result).unwrap();
+    let offers = Offer::find_by_provider_id(&conn, result.offers[0].provider_id.as_ref().unwrap()).unwrap();
+
+    assert_eq!(1, offers.len());
+    assert_eq!(Some(request_id), offers[0].request_id);
+    assert_eq!(194.56, offers[0].total_price);
+    assert_eq!(2, offers[0].slices.len());
+    assert_eq!(Some("Berlin Tegel".to_string()), offers[0].slices[0].segments[0].legs[0].origin_name);
+    assert_eq!(vec!("ZNN32RT", "ONNRT"), offers[0].pricings[0].basis_codes());
+    assert_eq!(result.offers[0].taxes.len(), offers[0].taxes.len());
+    assert_eq!(result.request.provider_request_id, Request::find(&conn, request_id).unwrap().unwrap().provider_request_id);
+}
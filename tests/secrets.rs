@@ -0,0 +1,52 @@
+extern crate flycheap;
+
+use std::env;
+
+use flycheap::secrets::{Secret, SecretSource};
+
+#[test]
+fn test_secret_is_redacted() {
+    let secret = Secret::new("GOOGLE_QPX_EXPRESS_KEY");
+
+    assert_eq!("Secret([REDACTED])", format!("{:?}", secret));
+    assert_eq!("[REDACTED]", format!("{}", secret));
+    assert_eq!("GOOGLE_QPX_EXPRESS_KEY", secret.expose());
+}
+
+#[test]
+fn test_resolve_from_env() {
+    env::set_var("FLYCHEAP_TEST_SECRET", "GOOGLE_QPX_EXPRESS_KEY\n");
+    let source = SecretSource { env: Some("FLYCHEAP_TEST_SECRET".to_string()), file: None, command: None };
+
+    assert_eq!("GOOGLE_QPX_EXPRESS_KEY", source.resolve().unwrap().expose());
+}
+
+#[test]
+fn test_resolve_from_command() {
+    let source = SecretSource { env: None, file: None, command: Some("echo GOOGLE_QPX_EXPRESS_KEY".to_string()) };
+
+    assert_eq!("GOOGLE_QPX_EXPRESS_KEY", source.resolve().unwrap().expose());
+}
+
+#[test]
+fn test_resolve_requires_exactly_one_source() {
+    let source = SecretSource { env: Some("HOME".to_string()), file: None, command: Some("echo KEY".to_string()) };
+
+    assert!(source.resolve().is_err());
+}
+
+#[test]
+fn test_validate_does_not_run_the_command() {
+    let marker = env::temp_dir().join("flycheap_secret_command_ran");
+    let source = SecretSource { env: None, file: None, command: Some(format!("touch {}", marker.display())) };
+
+    assert!(source.validate().is_ok());
+    assert!(!marker.exists());
+}
+
+#[test]
+fn test_validate_requires_exactly_one_source() {
+    let source = SecretSource { env: Some("HOME".to_string()), file: None, command: Some("echo KEY".to_string()) };
+
+    assert!(source.validate().is_err());
+}
@@ -0,0 +1,112 @@
+extern crate flycheap;
+extern crate rusqlite;
+extern crate time;
+
+use time::Duration;
+
+mod common;
+
+use common::{session, trip, watch};
+
+#[test]
+fn test_budget_is_split_by_share() {
+    let mut easter = watch("easter", vec!(trip("TXL", "OTP", &["2999-03-28", "2999-03-29"])));
+    easter.share = Some(2);
+    let summer = watch("summer", vec!(trip("MUC", "JFK", &["2999-07-01", "2999-07-02", "2999-07-03"])));
+    let session = session(30, vec!(easter, summer));
+
+    assert_eq!(20, session.budget(&session.watches[0]));
+    assert_eq!(10, session.budget(&session.watches[1]));
+    assert_eq!(10, session.request_sets_per_day(&session.watches[0]));
+    assert_eq!(3, session.request_sets_per_day(&session.watches[1]));
+}
+
+#[test]
+fn test_requests_are_named_after_their_watch() {
+    let easter = watch("easter", vec!(trip("TXL", "OTP", &["2999-03-28", "2999-03-29"])));
+    let session = session(24, vec!(easter));
+
+    let requests = session.requests(&session.watches[0]);
+
+    assert_eq!(2, requests.len());
+    assert!(requests.iter().all(|request| request.name == "easter"));
+}
+
+#[test]
+fn test_next_watches_share_the_earliest_run() {
+    let easter = watch("easter", vec!(trip("TXL", "OTP", &["2999-03-28"])));
+    let summer = watch("summer", vec!(trip("MUC", "JFK", &["2999-07-01"])));
+    let session = session(48, vec!(easter, summer));
+
+    let names: Vec<&str> = session.next_watches().unwrap().iter().map(|watch| watch.name.as_str()).collect();
+
+    assert_eq!(vec!("easter", "summer"), names);
+}
+
+#[test]
+fn test_next_watches_picks_the_most_frequent_watch() {
+    let mut hourly = watch("hourly", vec!(trip("TXL", "OTP", &["2999-03-28"])));
+    hourly.share = Some(24);
+    let daily = watch("daily", vec!(trip("MUC", "JFK", &["2999-07-01"])));
+    let session = session(25, vec!(daily, hourly));
+
+    let names: Vec<&str> = session.next_watches().unwrap().iter().map(|watch| watch.name.as_str()).collect();
+
+    assert_eq!(vec!("hourly"), names);
+}
+
+#[test]
+fn test_next_watches_skips_idle_watches() {
+    let easter = watch("easter", vec!(trip("TXL", "OTP", &["2999-03-28"])));
+    let past = watch("past", vec!(trip("MUC", "JFK", &["2001-07-01"])));
+    let session = session(24, vec!(past, easter));
+
+    let idle: Vec<&str> = session.idle_watches().iter().map(|watch| watch.name.as_str()).collect();
+    let next: Vec<&str> = session.next_watches().unwrap().iter().map(|watch| watch.name.as_str()).collect();
+
+    assert_eq!(vec!("past"), idle);
+    assert_eq!(vec!("easter"), next);
+}
+
+#[test]
+fn test_next_watches_without_any_runnable_watch() {
+    let costly = watch("costly", vec!(trip("TXL", "OTP", &["2999-03-28", "2999-03-29", "2999-03-30"])));
+    let session = session(2, vec!(costly));
+
+    assert_eq!(1, session.idle_watches().len());
+    assert!(session.next_watches().is_err());
+}
+
+#[test]
+fn test_next_runs_are_spaced_by_the_duration_per_request() {
+    let easter = watch("easter", vec!(trip("TXL", "OTP", &["2999-03-28", "2999-03-29"])));
+    let session = session(24, vec!(easter));
+    let watch = &session.watches[0];
+
+    let runs = session.next_runs_at(watch, 3).unwrap();
+
+    assert_eq!(3, runs.len());
+    assert_eq!(session.next_run_at(watch).unwrap(), runs[0]);
+    assert_eq!(Duration::hours(2), session.duration_per_request(watch).unwrap());
+    assert_eq!(Duration::hours(2), runs[1] - runs[0]);
+    assert_eq!(Duration::hours(2), runs[2] - runs[1]);
+}
+
+#[test]
+fn test_next_runs_of_an_idle_watch() {
+    let past = watch("past", vec!(trip("MUC", "JFK", &["2001-07-01"])));
+    let session = session(24, vec!(past));
+
+    assert!(session.next_runs_at(&session.watches[0], 3).is_err());
+}
+
+#[test]
+fn test_runs_are_scheduled_on_whole_seconds() {
+    let dates = ["2999-03-01", "2999-03-02", "2999-03-03", "2999-03-04", "2999-03-05", "2999-03-06", "2999-03-07"];
+    let session = session(49, vec!(watch("weekly", vec!(trip("TXL", "OTP", &dates)))));
+    let watch = &session.watches[0];
+
+    assert_eq!(7, session.request_sets_per_day(watch));
+    assert_eq!(Duration::seconds(12342), session.duration_per_request(watch).unwrap());
+    assert!(session.next_runs_at(watch, 7).unwrap().iter().all(|run_at| run_at.tm_nsec == 0));
+}
@@ -0,0 +1,69 @@
+extern crate flycheap;
+extern crate rusqlite;
+
+mod common;
+
+use common::{trip, watch};
+
+#[test]
+fn test_total_calls_skips_returns_before_departures() {
+    let outbound = trip("TXL", "OTP", &["2999-03-28", "2999-04-05"]);
+    let inbound = trip("OTP", "TXL", &["2999-04-03"]);
+
+    assert_eq!(1, watch("txl_to_otp", vec!(outbound, inbound)).total_calls());
+}
+
+#[test]
+fn test_total_calls_with_stay_lengths() {
+    let mut round_trip = trip("TXL", "OTP", &["2999-03-28", "2999-03-29"]);
+    round_trip.min_stay = Some(2);
+    round_trip.max_stay = Some(4);
+
+    assert_eq!(6, watch("txl_to_otp", vec!(round_trip)).total_calls());
+}
+
+#[test]
+fn test_invalid_stay_lengths() {
+    let mut round_trip = trip("TXL", "OTP", &["2999-03-28"]);
+    round_trip.min_stay = Some(5);
+    round_trip.max_stay = Some(2);
+
+    assert!(round_trip.stay().is_err());
+}
+
+#[test]
+fn test_requests_carry_the_watch_filters() {
+    let mut outbound = trip("TXL", "OTP", &["2999-03-28"]);
+    outbound.cabin = Some("BUSINESS".to_string());
+    outbound.max_connection_duration = Some(180);
+
+    let mut watch = watch("txl_to_otp", vec!(outbound, trip("OTP", "TXL", &["2999-04-03"])));
+    watch.max_price = Some("EUR400".to_string());
+    watch.refundable = Some(true);
+    watch.solutions = Some(20);
+
+    let request = watch.requests("DE").remove(0);
+
+    assert_eq!(Some("EUR400".to_string()), request.max_price);
+    assert_eq!(Some(true), request.refundable);
+    assert_eq!(Some(20), request.solutions);
+    assert_eq!(Some("BUSINESS".to_string()), request.slices[0].preferred_cabin);
+    assert_eq!(Some(180), request.slices[0].max_connection_duration);
+    assert_eq!(None, request.slices[1].preferred_cabin);
+    assert_eq!(None, request.slices[1].max_connection_duration);
+}
+
+#[test]
+fn test_requests_use_the_stops_of_each_trip() {
+    let mut outbound = trip("TXL", "OTP", &["2999-03-28"]);
+    outbound.max_stops = Some(1);
+    outbound.permitted_connections = Some(vec!("VIE".to_string()));
+    outbound.prohibited_connections = Some(vec!("IST".to_string()));
+
+    let request = watch("txl_to_otp", vec!(outbound, trip("OTP", "TXL", &["2999-04-03"]))).requests("DE").remove(0);
+
+    assert_eq!(1, request.slices[0].max_stops);
+    assert_eq!(Some(vec!("VIE".to_string())), request.slices[0].permitted_connection_points);
+    assert_eq!(Some(vec!("IST".to_string())), request.slices[0].prohibited_connection_points);
+    assert_eq!(0, request.slices[1].max_stops);
+}